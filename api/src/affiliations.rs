@@ -0,0 +1,112 @@
+//! Lookup of the organizations a user is affiliated with, as an owner or as a member.
+
+use hub_core::{chrono::Utc, uuid::Uuid};
+use sea_orm::{prelude::*, sea_query::OnConflict, JoinType, QueryOrder, QuerySelect, Set};
+
+use crate::entities::{members, organization_selections, organizations, owners};
+
+/// The affiliations of a user with organizations that are still active, most recent first.
+#[derive(Debug, Clone, Default)]
+pub struct Affiliations {
+    /// The organizations the user owns.
+    pub owners: Vec<owners::Model>,
+    /// The organizations the user is a member of.
+    pub members: Vec<members::Model>,
+}
+
+impl Affiliations {
+    /// The IDs of the organizations the user is affiliated with, owned organizations first.
+    #[must_use]
+    pub fn organization_ids(&self) -> Vec<Uuid> {
+        self.owners
+            .iter()
+            .map(|o| o.organization_id)
+            .chain(self.members.iter().map(|m| m.organization_id))
+            .collect()
+    }
+
+    /// Whether the user is affiliated with the organization.
+    #[must_use]
+    pub fn contains(&self, organization: Uuid) -> bool {
+        self.owners
+            .iter()
+            .any(|o| o.organization_id == organization)
+            || self
+                .members
+                .iter()
+                .any(|m| m.organization_id == organization)
+    }
+}
+
+/// Finds the active affiliations of a user. Memberships that have been revoked or deactivated and organizations that have been deactivated are excluded.
+///
+/// # Errors
+/// This function fails if the database queries fail.
+pub async fn find_active(conn: &DatabaseConnection, user: Uuid) -> Result<Affiliations, DbErr> {
+    let owners = owners::Entity::find_by_user(user)
+        .join(JoinType::InnerJoin, owners::Relation::Organizations.def())
+        .filter(organizations::Column::DeactivatedAt.is_null())
+        .order_by_desc(owners::Column::CreatedAt)
+        .all(conn)
+        .await?;
+
+    let members = members::Entity::find_active_by_user(user)
+        .join(JoinType::InnerJoin, members::Relation::Organizations.def())
+        .filter(organizations::Column::DeactivatedAt.is_null())
+        .order_by_desc(members::Column::CreatedAt)
+        .all(conn)
+        .await?;
+
+    Ok(Affiliations { owners, members })
+}
+
+/// Records the organization a user selected so it can be restored the next time they log in.
+///
+/// # Errors
+/// This function fails if the database query fails.
+pub async fn remember_selection(
+    conn: &DatabaseConnection,
+    user: Uuid,
+    organization: Uuid,
+) -> Result<(), DbErr> {
+    organization_selections::Entity::insert(organization_selections::ActiveModel {
+        user_id: Set(user),
+        organization_id: Set(organization),
+        selected_at: Set(Utc::now().into()),
+    })
+    .on_conflict(
+        OnConflict::column(organization_selections::Column::UserId)
+            .update_columns([
+                organization_selections::Column::OrganizationId,
+                organization_selections::Column::SelectedAt,
+            ])
+            .to_owned(),
+    )
+    .exec(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Forgets the organization a user last selected so it is no longer restored when they log in.
+///
+/// # Errors
+/// This function fails if the database query fails.
+pub async fn forget_selection(conn: &DatabaseConnection, user: Uuid) -> Result<(), DbErr> {
+    organization_selections::Entity::delete_by_id(user)
+        .exec(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Fetches the organization a user last selected, if any.
+///
+/// # Errors
+/// This function fails if the database query fails.
+pub async fn last_selection(conn: &DatabaseConnection, user: Uuid) -> Result<Option<Uuid>, DbErr> {
+    Ok(organization_selections::Entity::find_by_id(user)
+        .one(conn)
+        .await?
+        .map(|selection| selection.organization_id))
+}
@@ -0,0 +1,325 @@
+//! Authentication of the users calling the API.
+//!
+//! The identity of the caller is established once per request by the [`Authenticate`] middleware and read by the [`UserID`](crate::UserID) and [`UserEmail`](crate::UserEmail) extractors. Requests carrying no credentials are anonymous, requests carrying invalid credentials are rejected.
+
+use std::{fs, path::PathBuf, sync::Arc};
+
+use hub_core::{
+    anyhow::{self, Context as _},
+    clap,
+    prelude::*,
+    url::Url,
+    uuid::Uuid,
+};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use ory_openapi_generated_client::apis::{
+    configuration::Configuration, frontend_api, Error as OryError,
+};
+use poem::{async_trait, http::StatusCode, Endpoint, Error, Middleware, Request, Result};
+use serde::Deserialize;
+
+use crate::UserID;
+
+const AUTHORIZATION_SCHEME: &str = "Bearer ";
+
+/// The asymmetric algorithms accepted for bearer tokens verified with a JSON Web Key Set.
+const JWKS_ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Arguments for configuring how callers of the API are authenticated. Exactly one of the modes must be configured.
+#[derive(Debug, clap::Args)]
+pub struct AuthArgs {
+    /// Trust the `X-USER-ID` and `X-USER-EMAIL` headers as set by an upstream gateway. Only enable this when the service is unreachable except through the gateway.
+    #[arg(long, env)]
+    pub trust_user_headers: bool,
+    /// A JSON Web Key Set file holding the public keys bearer tokens are signed with.
+    #[arg(long, env)]
+    pub jwt_jwks_file: Option<PathBuf>,
+    /// A shared secret bearer tokens are signed with using HS256.
+    #[arg(long, env)]
+    pub jwt_secret: Option<String>,
+    /// The issuer bearer tokens must be issued by.
+    #[arg(long, env)]
+    pub jwt_issuer: Option<String>,
+    /// The audience bearer tokens must be issued for.
+    #[arg(long, env)]
+    pub jwt_audience: Option<String>,
+    /// The public URL of the Ory Kratos instance sessions are verified with.
+    #[arg(long, env)]
+    pub kratos_public_url: Option<Url>,
+}
+
+/// The authenticated caller of a request.
+#[derive(Debug, Clone, Default)]
+pub struct Identity {
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+}
+
+/// The claims read from a bearer token.
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    email: Option<String>,
+}
+
+/// Verifies the credentials of a request and derives the identity of the caller from them.
+pub enum Authenticator {
+    /// Reads the identity from the `X-USER-ID` and `X-USER-EMAIL` headers without verification.
+    Headers,
+    /// Verifies bearer tokens signed with one of the keys of a JSON Web Key Set. The algorithm of `validation` is replaced by the one of each token.
+    Jwks {
+        keys: JwkSet,
+        validation: Validation,
+    },
+    /// Verifies bearer tokens signed with a shared secret.
+    Secret {
+        key: DecodingKey,
+        validation: Validation,
+    },
+    /// Verifies Ory Kratos session tokens and cookies.
+    Kratos(Configuration),
+}
+
+impl Authenticator {
+    /// Builds the authenticator of the mode configured by `args`.
+    ///
+    /// # Errors
+    /// This function fails if no mode or more than one mode is configured, or if the JSON Web Key Set cannot be read.
+    pub fn new(args: AuthArgs) -> anyhow::Result<Self> {
+        let AuthArgs {
+            trust_user_headers,
+            jwt_jwks_file,
+            jwt_secret,
+            jwt_issuer,
+            jwt_audience,
+            kratos_public_url,
+        } = args;
+
+        let validation = |algorithm| {
+            let mut validation = Validation::new(algorithm);
+
+            if let Some(issuer) = &jwt_issuer {
+                validation.set_issuer(&[issuer]);
+            }
+
+            match &jwt_audience {
+                Some(audience) => validation.set_audience(&[audience]),
+                None => validation.validate_aud = false,
+            }
+
+            validation
+        };
+
+        match (
+            trust_user_headers,
+            jwt_jwks_file,
+            jwt_secret,
+            kratos_public_url,
+        ) {
+            (true, None, None, None) => {
+                warn!("trusting X-USER-ID and X-USER-EMAIL headers without verification");
+
+                Ok(Self::Headers)
+            },
+            (false, Some(path), None, None) => {
+                let keys = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read JWKS file {}", path.display()))?;
+                let keys = serde_json::from_str(&keys).context("failed to parse JWKS file")?;
+
+                Ok(Self::Jwks {
+                    keys,
+                    validation: validation(Algorithm::RS256),
+                })
+            },
+            (false, None, Some(secret), None) => Ok(Self::Secret {
+                key: DecodingKey::from_secret(secret.as_bytes()),
+                validation: validation(Algorithm::HS256),
+            }),
+            (false, None, None, Some(url)) => Ok(Self::Kratos(Configuration {
+                base_path: url.as_str().trim_end_matches('/').to_string(),
+                ..Configuration::default()
+            })),
+            (false, None, None, None) => Err(anyhow::anyhow!(
+                "no authentication configured, set one of --jwt-jwks-file, --jwt-secret, \
+                 --kratos-public-url or --trust-user-headers"
+            )),
+            _ => Err(anyhow::anyhow!(
+                "only one of --jwt-jwks-file, --jwt-secret, --kratos-public-url and \
+                 --trust-user-headers can be set"
+            )),
+        }
+    }
+
+    /// Derives the identity of the caller of a request. Requests without credentials are anonymous.
+    ///
+    /// # Errors
+    /// This function fails with `401 Unauthorized` if the credentials of the request are invalid, or with `500 Internal Server Error` if Ory Kratos cannot be reached.
+    pub async fn identify(&self, req: &Request) -> Result<Identity> {
+        match self {
+            Self::Headers => {
+                let UserID(user_id) = header(req, "X-USER-ID")
+                    .map_or(Ok(UserID(None)), UserID::try_from)
+                    .map_err(|_| unauthorized())?;
+
+                Ok(Identity {
+                    user_id,
+                    email: header(req, "X-USER-EMAIL").map(ToString::to_string),
+                })
+            },
+            Self::Jwks { keys, validation } => {
+                let Some(token) = bearer_token(req) else {
+                    return Ok(Identity::default());
+                };
+
+                let header = jsonwebtoken::decode_header(token).map_err(|_| unauthorized())?;
+
+                if !JWKS_ALGORITHMS.contains(&header.alg) {
+                    return Err(unauthorized());
+                }
+
+                let kid = header.kid.ok_or_else(unauthorized)?;
+                let jwk = keys.find(&kid).ok_or_else(unauthorized)?;
+
+                // a key restricted to one algorithm must not verify tokens signed with another
+                if jwk
+                    .common
+                    .algorithm
+                    .map_or(false, |algorithm| algorithm != header.alg)
+                {
+                    return Err(unauthorized());
+                }
+
+                let key = DecodingKey::from_jwk(jwk).map_err(|_| unauthorized())?;
+
+                // every algorithm of a validation must belong to the family of the key
+                let mut validation = validation.clone();
+                validation.algorithms = vec![header.alg];
+
+                decode(token, &key, &validation)
+            },
+            Self::Secret { key, validation } => match bearer_token(req) {
+                Some(token) => decode(token, key, validation),
+                None => Ok(Identity::default()),
+            },
+            Self::Kratos(config) => {
+                let session_token = header(req, "X-Session-Token");
+                let cookie = header(req, "Cookie");
+
+                if session_token.is_none() && cookie.is_none() {
+                    return Ok(Identity::default());
+                }
+
+                let session = match frontend_api::to_session(config, session_token, cookie).await {
+                    Ok(session) => session,
+                    Err(OryError::ResponseError(res)) if res.status.as_u16() == 401 => {
+                        // browsers send cookies unrelated to Kratos, only an explicit session token is rejected
+                        return match session_token {
+                            Some(_) => Err(unauthorized()),
+                            None => Ok(Identity::default()),
+                        };
+                    },
+                    Err(e) => {
+                        error!("failed to verify kratos session: {e}");
+
+                        return Err(Error::from_status(StatusCode::INTERNAL_SERVER_ERROR));
+                    },
+                };
+
+                if session.active != Some(true) {
+                    return Err(unauthorized());
+                }
+
+                let identity = session.identity;
+                let user_id = Uuid::from_str(&identity.id).map_err(|_| unauthorized())?;
+                let email = identity
+                    .traits
+                    .as_ref()
+                    .and_then(|traits| traits.get("email"))
+                    .and_then(serde_json::Value::as_str)
+                    .map(ToString::to_string);
+
+                Ok(Identity {
+                    user_id: Some(user_id),
+                    email,
+                })
+            },
+        }
+    }
+}
+
+fn header<'a>(req: &'a Request, name: &str) -> Option<&'a str> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn bearer_token(req: &Request) -> Option<&str> {
+    header(req, "Authorization").and_then(|value| value.strip_prefix(AUTHORIZATION_SCHEME))
+}
+
+fn decode(token: &str, key: &DecodingKey, validation: &Validation) -> Result<Identity> {
+    let Claims { sub, email } = jsonwebtoken::decode::<Claims>(token, key, validation)
+        .map_err(|_| unauthorized())?
+        .claims;
+
+    let user_id = Uuid::from_str(&sub).map_err(|_| unauthorized())?;
+
+    Ok(Identity {
+        user_id: Some(user_id),
+        email,
+    })
+}
+
+fn unauthorized() -> Error {
+    Error::from_string("invalid credentials", StatusCode::UNAUTHORIZED)
+}
+
+/// Middleware that authenticates every request with an [`Authenticator`] before passing it on.
+#[derive(Clone)]
+pub struct Authenticate(Arc<Authenticator>);
+
+impl Authenticate {
+    #[must_use]
+    pub fn new(authenticator: Arc<Authenticator>) -> Self {
+        Self(authenticator)
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for Authenticate {
+    type Output = AuthenticateEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        AuthenticateEndpoint {
+            ep,
+            authenticator: self.0.clone(),
+        }
+    }
+}
+
+/// The endpoint produced by the [`Authenticate`] middleware.
+pub struct AuthenticateEndpoint<E> {
+    ep: E,
+    authenticator: Arc<Authenticator>,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for AuthenticateEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let identity = self.authenticator.identify(&req).await?;
+        req.extensions_mut().insert(identity);
+
+        self.ep.call(req).await
+    }
+}
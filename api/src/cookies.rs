@@ -0,0 +1,160 @@
+//! The `_hub_org` cookie holding the organization selected in the browser.
+//!
+//! The cookie is signed with a server key so the organization it names can only have been selected through hub-orgs.
+
+use std::time::Duration;
+
+use hub_core::{
+    anyhow::{anyhow, Result},
+    clap,
+    prelude::*,
+    uuid::Uuid,
+};
+use poem::web::cookie::{Cookie, CookieJar, CookieKey, SameSite};
+
+/// The name of the cookie holding the selected organization.
+pub const HUB_ORG_COOKIE_NAME: &str = "_hub_org";
+
+/// The minimum length, in bytes, of the key the cookie is signed with.
+const MIN_KEY_LENGTH: usize = 32;
+
+/// The `SameSite` policy of the cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SameSitePolicy {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<SameSitePolicy> for SameSite {
+    fn from(value: SameSitePolicy) -> Self {
+        match value {
+            SameSitePolicy::Strict => Self::Strict,
+            SameSitePolicy::Lax => Self::Lax,
+            SameSitePolicy::None => Self::None,
+        }
+    }
+}
+
+/// Arguments for configuring the `_hub_org` cookie
+#[derive(Debug, clap::Args)]
+pub struct CookieArgs {
+    /// The domain the cookie is scoped to. The cookie is host-only when it is not set.
+    #[arg(long, env)]
+    pub hub_org_cookie_domain: Option<String>,
+    /// Only send the cookie over HTTPS.
+    #[arg(long, env, default_value_t = true, action = clap::ArgAction::Set)]
+    pub hub_org_cookie_secure: bool,
+    /// The `SameSite` policy of the cookie.
+    #[arg(long, env, value_enum, default_value_t = SameSitePolicy::Lax)]
+    pub hub_org_cookie_same_site: SameSitePolicy,
+    /// The lifetime of the cookie in seconds. The cookie expires with the browser session when it is not set.
+    #[arg(long, env)]
+    pub hub_org_cookie_max_age: Option<u64>,
+    /// The key the cookie is signed with, at least 32 bytes long.
+    #[arg(long, env)]
+    pub hub_org_cookie_key: String,
+}
+
+/// Reads and writes the signed `_hub_org` cookie.
+#[derive(Clone)]
+pub struct OrganizationCookie {
+    key: CookieKey,
+    domain: Option<String>,
+    secure: bool,
+    same_site: SameSite,
+    max_age: Option<Duration>,
+}
+
+impl OrganizationCookie {
+    /// Builds the cookie settings from `args`.
+    ///
+    /// # Errors
+    /// This function fails if the signing key is shorter than 32 bytes, or if a `SameSite` policy of `None` is used without the secure flag, which browsers reject.
+    pub fn new(args: CookieArgs) -> Result<Self> {
+        let CookieArgs {
+            hub_org_cookie_domain,
+            hub_org_cookie_secure,
+            hub_org_cookie_same_site,
+            hub_org_cookie_max_age,
+            hub_org_cookie_key,
+        } = args;
+
+        if hub_org_cookie_key.len() < MIN_KEY_LENGTH {
+            return Err(anyhow!(
+                "the hub org cookie key must be at least {MIN_KEY_LENGTH} bytes long"
+            ));
+        }
+
+        if hub_org_cookie_same_site == SameSitePolicy::None && !hub_org_cookie_secure {
+            return Err(anyhow!(
+                "a SameSite policy of None requires the hub org cookie to be secure"
+            ));
+        }
+
+        if !hub_org_cookie_secure {
+            warn!("the hub org cookie is not restricted to HTTPS");
+        }
+
+        Ok(Self {
+            key: CookieKey::derive_from(hub_org_cookie_key.as_bytes()),
+            domain: hub_org_cookie_domain,
+            secure: hub_org_cookie_secure,
+            same_site: hub_org_cookie_same_site.into(),
+            max_age: hub_org_cookie_max_age.map(Duration::from_secs),
+        })
+    }
+
+    fn cookie(&self, value: String) -> Cookie {
+        let mut cookie = Cookie::new_with_str(HUB_ORG_COOKIE_NAME, value);
+        cookie.set_path("/");
+        cookie.set_http_only(Some(true));
+        cookie.set_secure(Some(self.secure));
+        cookie.set_same_site(Some(self.same_site));
+
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain);
+        }
+
+        if let Some(max_age) = self.max_age {
+            cookie.set_max_age(max_age);
+        }
+
+        cookie
+    }
+
+    /// Selects an organization by adding the signed cookie to the jar.
+    pub fn set(&self, cookie_jar: &CookieJar, organization: Uuid) {
+        cookie_jar
+            .signed_jar(&self.key)
+            .add(self.cookie(organization.to_string()));
+    }
+
+    /// The value of the `Set-Cookie` header selecting an organization, for responses that do not go through a cookie jar.
+    #[must_use]
+    pub fn header_value(&self, organization: Uuid) -> Option<String> {
+        let cookie_jar = CookieJar::default();
+        self.set(&cookie_jar, organization);
+
+        cookie_jar
+            .get(HUB_ORG_COOKIE_NAME)
+            .map(|cookie| cookie.to_string())
+    }
+
+    /// Clears the selected organization by replacing the cookie with one that expires immediately.
+    pub fn remove(&self, cookie_jar: &CookieJar) {
+        let mut cookie = self.cookie(String::new());
+        cookie.set_max_age(Duration::ZERO);
+
+        cookie_jar.add(cookie);
+    }
+
+    /// Reads the selected organization from the jar. Cookies with an invalid signature are ignored.
+    #[must_use]
+    pub fn get(&self, cookie_jar: &CookieJar) -> Option<Uuid> {
+        cookie_jar
+            .signed_jar(&self.key)
+            .get(HUB_ORG_COOKIE_NAME)
+            .and_then(|cookie| Uuid::from_str(cookie.value_str()).ok())
+    }
+}
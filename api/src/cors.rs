@@ -0,0 +1,117 @@
+//! The CORS policy of the routes called from browsers.
+
+use hub_core::clap;
+use poem::middleware::Cors;
+
+/// Arguments for configuring the CORS policy of the `/graphql` and `/browser` routes
+#[derive(Debug, Clone, clap::Args)]
+pub struct CorsArgs {
+    /// The origins allowed to make credentialed cross-origin requests. Cross-origin requests are rejected when none are set.
+    #[arg(long, env, value_delimiter = ',')]
+    pub cors_allowed_origins: Vec<String>,
+    /// The HTTP methods allowed in cross-origin requests.
+    #[arg(long, env, value_delimiter = ',', default_value = "GET,POST,OPTIONS")]
+    pub cors_allowed_methods: Vec<String>,
+    /// The request headers allowed in cross-origin requests.
+    #[arg(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "authorization,content-type,x-session-token"
+    )]
+    pub cors_allowed_headers: Vec<String>,
+    /// How long, in seconds, browsers may cache the result of a preflight request.
+    #[arg(long, env, default_value_t = 3600)]
+    pub cors_max_age: i32,
+}
+
+impl CorsArgs {
+    /// Builds the CORS middleware. Only the configured origins are allowed, an empty list allows no cross-origin requests rather than every origin.
+    #[must_use]
+    pub fn policy(&self) -> Cors {
+        Cors::new()
+            .allow_credentials(true)
+            .allow_origins(self.cors_allowed_origins.iter().map(String::as_str))
+            .allow_origins_fn(|_| false)
+            .allow_methods(self.cors_allowed_methods.iter().map(String::as_str))
+            .allow_headers(self.cors_allowed_headers.iter().map(String::as_str))
+            .max_age(self.cors_max_age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poem::{
+        endpoint::make_sync,
+        http::{header, StatusCode},
+        test::TestClient,
+        EndpointExt,
+    };
+
+    use super::CorsArgs;
+
+    const ALLOWED_ORIGIN: &str = "https://hub.holaplex.com";
+
+    fn client() -> TestClient<impl poem::Endpoint> {
+        let args = CorsArgs {
+            cors_allowed_origins: vec![ALLOWED_ORIGIN.to_string()],
+            cors_allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            cors_allowed_headers: vec!["content-type".to_string()],
+            cors_max_age: 3600,
+        };
+
+        TestClient::new(make_sync(|_| "ok").with(args.policy()))
+    }
+
+    #[async_std::test]
+    async fn allows_credentials_from_allowed_origins() {
+        let resp = client()
+            .post("/")
+            .header(header::ORIGIN, ALLOWED_ORIGIN)
+            .send()
+            .await;
+
+        resp.assert_status_is_ok();
+        resp.assert_header(header::ACCESS_CONTROL_ALLOW_ORIGIN, ALLOWED_ORIGIN);
+        resp.assert_header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+    }
+
+    #[async_std::test]
+    async fn rejects_other_origins() {
+        let resp = client()
+            .post("/")
+            .header(header::ORIGIN, "https://evil.example")
+            .send()
+            .await;
+
+        resp.assert_status(StatusCode::FORBIDDEN);
+        resp.assert_header_is_not_exist(header::ACCESS_CONTROL_ALLOW_ORIGIN);
+        resp.assert_header_is_not_exist(header::ACCESS_CONTROL_ALLOW_CREDENTIALS);
+
+        let resp = client()
+            .options("/")
+            .header(header::ORIGIN, "https://evil.example")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .send()
+            .await;
+
+        resp.assert_status(StatusCode::FORBIDDEN);
+        resp.assert_header_is_not_exist(header::ACCESS_CONTROL_ALLOW_ORIGIN);
+    }
+
+    #[async_std::test]
+    async fn answers_preflight_requests_of_allowed_origins() {
+        let resp = client()
+            .options("/")
+            .header(header::ORIGIN, ALLOWED_ORIGIN)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .send()
+            .await;
+
+        resp.assert_status_is_ok();
+        resp.assert_header(header::ACCESS_CONTROL_ALLOW_ORIGIN, ALLOWED_ORIGIN);
+        resp.assert_header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+        resp.assert_header(header::ACCESS_CONTROL_MAX_AGE, "3600");
+    }
+}
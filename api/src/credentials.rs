@@ -0,0 +1,106 @@
+//! Secret tokens of project API credentials. Only a SHA-256 hash of each token is stored, the token itself is returned once when the credential is created.
+
+use hub_core::{
+    anyhow::Result,
+    chrono::{Duration, Utc},
+};
+use rand::{rngs::OsRng, RngCore};
+use sea_orm::{prelude::*, Set};
+use sha2::{Digest, Sha256};
+
+use crate::entities::{api_credentials, projects};
+
+/// The prefix of every API credential token, making leaked tokens easy to recognize.
+pub const TOKEN_PREFIX: &str = "hub_";
+
+/// The number of leading characters of a token that are stored in clear to identify the credential.
+const VISIBLE_LENGTH: usize = TOKEN_PREFIX.len() + 8;
+
+/// The number of random bytes in a token.
+const SECRET_BYTES: usize = 32;
+
+/// The number of seconds after which a new use of a credential is recorded, so verifying a token does not write to the database on every request.
+const LAST_USED_INTERVAL: i64 = 60;
+
+/// A newly generated API credential token.
+#[derive(Debug, Clone)]
+pub struct GeneratedToken {
+    /// The secret token, to be returned to the user and never stored.
+    pub token: String,
+    /// The leading characters of the token, safe to store and display.
+    pub prefix: String,
+    /// The hash of the token persisted for verification.
+    pub hash: String,
+}
+
+/// Generates a new random API credential token.
+#[must_use]
+pub fn generate() -> GeneratedToken {
+    let mut secret = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut secret);
+
+    let token = format!("{TOKEN_PREFIX}{}", hex::encode(secret));
+
+    GeneratedToken {
+        prefix: token[..VISIBLE_LENGTH].to_string(),
+        hash: hash(&token),
+        token,
+    }
+}
+
+/// Hashes an API credential token for storage and lookup.
+#[must_use]
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// A successfully verified API credential along with the project it grants access to.
+#[derive(Debug, Clone)]
+pub struct Verified {
+    pub credential: api_credentials::Model,
+    pub project: projects::Model,
+}
+
+/// Looks up the active credential matching `token` and records when it was last used, at most once a minute. Returns `None` when the token is unknown, revoked or belongs to a deactivated project.
+///
+/// # Errors
+/// This function fails if the database queries fail.
+pub async fn verify(conn: &DatabaseConnection, token: &str) -> Result<Option<Verified>> {
+    if !token.starts_with(TOKEN_PREFIX) {
+        return Ok(None);
+    }
+
+    let Some((credential, Some(project))) =
+        api_credentials::Entity::find_active_by_secret_hash(&hash(token))
+            .find_also_related(projects::Entity)
+            .one(conn)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    if project.deactivated_at.is_some() {
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+
+    if credential.last_used_at.map_or(false, |at| {
+        now.signed_duration_since(at) < Duration::seconds(LAST_USED_INTERVAL)
+    }) {
+        return Ok(Some(Verified {
+            credential,
+            project,
+        }));
+    }
+
+    let mut active_model: api_credentials::ActiveModel = credential.into();
+    active_model.last_used_at = Set(Some(now.into()));
+
+    let credential = active_model.update(conn).await?;
+
+    Ok(Some(Verified {
+        credential,
+        project,
+    }))
+}
@@ -0,0 +1,153 @@
+//! Credit balances of organizations, kept as an append-only ledger.
+//!
+//! The balance of an organization is the sum of the amounts of its `credit_ledger` entries. Entries are never updated or removed; every change to a balance is recorded as a new entry with a reference that is unique within the organization, so an operation replayed with the same reference is only recorded once.
+
+use hub_core::clap;
+use sea_orm::{
+    prelude::*, ConnectionTrait, DbBackend, QuerySelect, Set, Statement, TransactionTrait,
+};
+
+use crate::{
+    entities::{credit_ledger, organizations, sea_orm_active_enums::CreditEntryKind},
+    events::Activity,
+};
+
+/// The credits deducted from an organization for each billed activity of its projects. Activities that cost `0` credits are not billed.
+#[derive(Debug, Clone, Copy, clap::Args)]
+pub struct CreditArgs {
+    #[arg(long, env, default_value_t = 0)]
+    pub credit_cost_drop_created: i64,
+
+    #[arg(long, env, default_value_t = 0)]
+    pub credit_cost_drop_minted: i64,
+
+    #[arg(long, env, default_value_t = 0)]
+    pub credit_cost_customer_created: i64,
+
+    #[arg(long, env, default_value_t = 0)]
+    pub credit_cost_wallet_created: i64,
+}
+
+impl CreditArgs {
+    /// The credits deducted for an activity.
+    #[must_use]
+    pub fn cost(&self, activity: Activity) -> i64 {
+        match activity {
+            Activity::DropCreated => self.credit_cost_drop_created,
+            Activity::DropMinted => self.credit_cost_drop_minted,
+            Activity::CustomerCreated => self.credit_cost_customer_created,
+            Activity::WalletCreated => self.credit_cost_wallet_created,
+        }
+    }
+}
+
+/// The unique index on the organization and reference of ledger entries.
+pub const REFERENCE_INDEX: &str = "credit_ledger_organization_id_reference_idx";
+
+/// The outcome of recording a deduction.
+#[derive(Debug, Clone)]
+pub enum Deduction {
+    /// The deduction was recorded in the ledger.
+    Recorded(credit_ledger::Model),
+    /// A ledger entry with the same reference already exists, nothing was recorded.
+    Duplicate,
+    /// The balance of the organization does not cover the deduction, nothing was recorded.
+    InsufficientBalance { balance: i64 },
+}
+
+/// Computes the credit balance of an organization from its ledger.
+///
+/// # Errors
+/// This function fails if the database query fails.
+pub async fn balance<C: ConnectionTrait>(conn: &C, organization: Uuid) -> Result<i64, DbErr> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT COALESCE(SUM(amount), 0)::bigint AS balance
+        FROM credit_ledger WHERE organization_id = $1;"#,
+        [organization.into()],
+    );
+
+    match conn.query_one(stmt).await? {
+        Some(row) => row.try_get("", "balance"),
+        None => Ok(0),
+    }
+}
+
+/// Records a deposit of `amount` credits made by `created_by` into the ledger of an organization.
+///
+/// # Errors
+/// This function fails if the database query fails, including when an entry with the same reference already exists for the organization, which violates [`REFERENCE_INDEX`].
+pub async fn deposit(
+    conn: &DatabaseConnection,
+    organization: Uuid,
+    amount: i64,
+    reference: String,
+    created_by: Uuid,
+) -> Result<credit_ledger::Model, DbErr> {
+    credit_ledger::ActiveModel {
+        organization_id: Set(organization),
+        kind: Set(CreditEntryKind::Deposit),
+        amount: Set(amount),
+        reference: Set(reference),
+        created_by: Set(Some(created_by)),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+}
+
+/// Records a deduction of `amount` credits from the ledger of an organization, unless it would make the balance negative.
+///
+/// The organization row is locked for the duration of the check so concurrent deductions cannot overdraw the balance together.
+///
+/// # Errors
+/// This function fails if the database queries fail.
+pub async fn deduct(
+    conn: &DatabaseConnection,
+    organization: Uuid,
+    amount: i64,
+    reference: String,
+) -> Result<Deduction, DbErr> {
+    let txn = conn.begin().await?;
+
+    organizations::Entity::find_by_id(organization)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    let duplicate = credit_ledger::Entity::find()
+        .filter(credit_ledger::Column::OrganizationId.eq(organization))
+        .filter(credit_ledger::Column::Reference.eq(reference.as_str()))
+        .one(&txn)
+        .await?
+        .is_some();
+
+    if duplicate {
+        txn.commit().await?;
+
+        return Ok(Deduction::Duplicate);
+    }
+
+    let balance = balance(&txn, organization).await?;
+
+    if balance < amount {
+        txn.commit().await?;
+
+        return Ok(Deduction::InsufficientBalance { balance });
+    }
+
+    let entry = credit_ledger::ActiveModel {
+        organization_id: Set(organization),
+        kind: Set(CreditEntryKind::Deduction),
+        amount: Set(-amount),
+        reference: Set(reference),
+        created_by: Set(None),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(Deduction::Recorded(entry))
+}
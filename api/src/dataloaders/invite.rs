@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
-use async_graphql::{dataloader::Loader as DataLoader, FieldError, Result};
+use async_graphql::dataloader::Loader as DataLoader;
 use poem::async_trait;
 use sea_orm::prelude::*;
 
-use crate::{db::Connection, entities::members};
+use crate::{
+    db::Connection,
+    entities::members,
+    errors::{ApiError, Result},
+};
 
 #[derive(Debug, Clone)]
 pub struct MemberLoader {
@@ -20,7 +24,7 @@ impl MemberLoader {
 
 #[async_trait]
 impl DataLoader<Uuid> for MemberLoader {
-    type Error = FieldError;
+    type Error = ApiError;
     type Value = members::Member;
 
     async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
-use async_graphql::{dataloader::Loader as DataLoader, FieldError, Result};
+use async_graphql::dataloader::Loader as DataLoader;
 use poem::async_trait;
 use sea_orm::prelude::*;
 
-use crate::{db::Connection, entities::invites};
+use crate::{
+    db::Connection,
+    entities::invites,
+    errors::{ApiError, Result},
+};
 
 #[derive(Debug, Clone)]
 pub struct InviteLoader {
@@ -20,7 +24,7 @@ impl InviteLoader {
 
 #[async_trait]
 impl DataLoader<Uuid> for InviteLoader {
-    type Error = FieldError;
+    type Error = ApiError;
     type Value = invites::Model;
 
     async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
@@ -1,11 +1,15 @@
 pub mod invite;
 pub mod member;
 pub mod organization;
+pub mod profile;
 pub mod project;
+pub mod stats;
 pub mod user;
 
 pub use invite::MemberLoader as InviteMemberLoader;
 pub use member::InviteLoader as MemberInviteLoader;
 pub use organization::Loader as OrganizationLoader;
+pub use profile::Loader as UserProfileLoader;
 pub use project::Loader as ProjectLoader;
+pub use stats::{OrganizationStatsLoader, ProjectStatsLoader};
 pub use user::{MembersLoader, OwnerLoader};
@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 
-use async_graphql::{dataloader::Loader as DataLoader, FieldError, Result};
+use async_graphql::dataloader::Loader as DataLoader;
 use poem::async_trait;
 use sea_orm::prelude::*;
 
 use crate::{
     db::Connection,
     entities::organizations::{Column, Entity, Organization},
+    errors::{ApiError, Result},
 };
 
 #[derive(Debug, Clone)]
@@ -23,7 +24,7 @@ impl Loader {
 
 #[async_trait]
 impl DataLoader<Uuid> for Loader {
-    type Error = FieldError;
+    type Error = ApiError;
     type Value = Organization;
 
     async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
@@ -0,0 +1,35 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::dataloader::Loader as DataLoader;
+use poem::async_trait;
+use sea_orm::prelude::*;
+
+use crate::{
+    errors::{ApiError, Result},
+    users::{UserDirectory, UserProfile},
+};
+
+#[derive(Clone)]
+pub struct Loader {
+    pub directory: Arc<dyn UserDirectory>,
+}
+
+impl Loader {
+    #[must_use]
+    pub fn new(directory: Arc<dyn UserDirectory>) -> Self {
+        Self { directory }
+    }
+}
+
+#[async_trait]
+impl DataLoader<Uuid> for Loader {
+    type Error = ApiError;
+    type Value = UserProfile;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        self.directory
+            .profiles(keys)
+            .await
+            .map_err(ApiError::internal)
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use async_graphql::{dataloader::Loader as DataLoader, FieldError, Result};
+use async_graphql::dataloader::Loader as DataLoader;
 use poem::async_trait;
 use sea_orm::prelude::*;
 
@@ -10,6 +10,7 @@ use crate::{
         projects::{Column, Entity},
         Project,
     },
+    errors::{ApiError, Result},
 };
 
 #[derive(Debug, Clone)]
@@ -26,7 +27,7 @@ impl Loader {
 
 #[async_trait]
 impl DataLoader<Uuid> for Loader {
-    type Error = FieldError;
+    type Error = ApiError;
     type Value = Project;
 
     async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
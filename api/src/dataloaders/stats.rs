@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use async_graphql::dataloader::Loader as DataLoader;
+use poem::async_trait;
+use sea_orm::{prelude::*, sea_query::Expr, FromQueryResult, JoinType, QuerySelect};
+
+use crate::{
+    db::Connection,
+    entities::{
+        project_stats::{self, OrganizationStats, ProjectStats},
+        projects,
+    },
+    errors::{ApiError, Result},
+};
+
+#[derive(Debug, Clone)]
+pub struct ProjectStatsLoader {
+    pub db: Connection,
+}
+
+impl ProjectStatsLoader {
+    #[must_use]
+    pub fn new(db: Connection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl DataLoader<Uuid> for ProjectStatsLoader {
+    type Error = ApiError;
+    type Value = ProjectStats;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let stats = project_stats::Entity::find()
+            .filter(project_stats::Column::ProjectId.is_in(keys.iter().map(ToOwned::to_owned)))
+            .all(self.db.get())
+            .await?;
+
+        Ok(stats
+            .into_iter()
+            .map(|s| (s.project_id, s.into()))
+            .collect())
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OrganizationStatsRow {
+    organization_id: Uuid,
+    drops: i64,
+    mints: i64,
+    customers: i64,
+    wallets: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrganizationStatsLoader {
+    pub db: Connection,
+}
+
+impl OrganizationStatsLoader {
+    #[must_use]
+    pub fn new(db: Connection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl DataLoader<Uuid> for OrganizationStatsLoader {
+    type Error = ApiError;
+    type Value = OrganizationStats;
+
+    async fn load(
+        &self,
+        organization_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let rows = projects::Entity::find()
+            .select_only()
+            .column(projects::Column::OrganizationId)
+            .column_as(Expr::cust("SUM(project_stats.drops)::bigint"), "drops")
+            .column_as(Expr::cust("SUM(project_stats.mints)::bigint"), "mints")
+            .column_as(
+                Expr::cust("SUM(project_stats.customers)::bigint"),
+                "customers",
+            )
+            .column_as(Expr::cust("SUM(project_stats.wallets)::bigint"), "wallets")
+            .join(JoinType::InnerJoin, projects::Relation::ProjectStats.def())
+            .filter(
+                projects::Column::OrganizationId
+                    .is_in(organization_ids.iter().map(ToOwned::to_owned)),
+            )
+            .group_by(projects::Column::OrganizationId)
+            .into_model::<OrganizationStatsRow>()
+            .all(self.db.get())
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |OrganizationStatsRow {
+                     organization_id,
+                     drops,
+                     mints,
+                     customers,
+                     wallets,
+                 }| {
+                    (organization_id, OrganizationStats {
+                        drops,
+                        mints,
+                        customers,
+                        wallets,
+                    })
+                },
+            )
+            .collect())
+    }
+}
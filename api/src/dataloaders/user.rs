@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use async_graphql::{dataloader::Loader as DataLoader, FieldError, Result};
+use async_graphql::dataloader::Loader as DataLoader;
 use poem::async_trait;
 use sea_orm::prelude::*;
 
@@ -10,6 +10,7 @@ use crate::{
         members::{Column as MColumn, Entity as MEntity, Member},
         owners::{Column, Entity, Owner},
     },
+    errors::{ApiError, Result},
 };
 
 #[derive(Debug, Clone)]
@@ -26,7 +27,7 @@ impl MembersLoader {
 
 #[async_trait]
 impl DataLoader<Uuid> for MembersLoader {
-    type Error = FieldError;
+    type Error = ApiError;
     type Value = Vec<Member>;
 
     async fn load(
@@ -65,7 +66,7 @@ impl OwnerLoader {
 
 #[async_trait]
 impl DataLoader<Uuid> for OwnerLoader {
-    type Error = FieldError;
+    type Error = ApiError;
     type Value = Owner;
 
     async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
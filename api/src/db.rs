@@ -3,6 +3,8 @@ use std::time::Duration;
 use hub_core::{anyhow::Result, clap, prelude::*};
 pub use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 
+use crate::telemetry;
+
 /// Arguments for establishing a database connection
 #[derive(Debug, clap::Args)]
 pub struct DbArgs {
@@ -18,6 +20,17 @@ pub struct DbArgs {
     pub database_url: String,
 }
 
+/// The connections of the database pool at a point in time.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStatus {
+    /// The connections currently open, idle or in use.
+    pub size: u32,
+    /// The open connections not in use.
+    pub idle: u32,
+    /// The maximum number of connections the pool opens.
+    pub max_connections: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct Connection(DatabaseConnection);
 
@@ -42,10 +55,12 @@ impl Connection {
             .idle_timeout(Duration::from_secs(idle_timeout))
             .clone();
 
-        let connection = sea_orm::Database::connect(options)
+        let mut connection = sea_orm::Database::connect(options)
             .await
             .context("failed to get database connection")?;
 
+        connection.set_metric_callback(telemetry::record_query);
+
         Ok(Self(connection))
     }
 
@@ -54,4 +69,16 @@ impl Connection {
     pub fn get(&self) -> &DatabaseConnection {
         &self.0
     }
+
+    /// Samples the connections of the database pool.
+    #[must_use]
+    pub fn pool_status(&self) -> PoolStatus {
+        let pool = self.0.get_postgres_connection_pool();
+
+        PoolStatus {
+            size: pool.size(),
+            idle: u32::try_from(pool.num_idle()).unwrap_or(u32::MAX),
+            max_connections: pool.options().get_max_connections(),
+        }
+    }
 }
@@ -5,7 +5,7 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use super::{members, organizations, sea_orm_active_enums::InviteStatus};
-use crate::AppContext;
+use crate::{errors::Result, users::UserProfile, AppContext};
 
 /// An invitation sent to join a Holaplex organization.
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, SimpleObject, Serialize, Deserialize)]
@@ -51,6 +51,16 @@ impl Model {
 
         organization_loader.load_one(self.organization_id).await
     }
+
+    /// The profile of the user who created the invitation, `null` when the profile cannot be found.
+    async fn creator(&self, ctx: &Context<'_>) -> Result<Option<UserProfile>> {
+        let AppContext {
+            user_profile_loader,
+            ..
+        } = ctx.data::<AppContext>()?;
+
+        user_profile_loader.load_one(self.created_by).await
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
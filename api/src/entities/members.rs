@@ -4,7 +4,7 @@ use async_graphql::*;
 use sea_orm::entity::prelude::*;
 
 use super::{invites, organizations::Organization};
-use crate::AppContext;
+use crate::{errors::Result, users::UserProfile, AppContext};
 
 #[derive(Clone, Copy, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "members")]
@@ -62,6 +62,16 @@ impl Member {
 
         member_invite_loader.load_one(self.invite_id).await
     }
+
+    /// The profile of the user who has been granted access to the Holaplex organization as a member, `null` when the profile cannot be found.
+    async fn user(&self, ctx: &Context<'_>) -> Result<Option<UserProfile>> {
+        let AppContext {
+            user_profile_loader,
+            ..
+        } = ctx.data::<AppContext>()?;
+
+        user_profile_loader.load_one(self.user_id).await
+    }
 }
 
 impl From<Model> for Member {
@@ -114,8 +124,6 @@ impl Entity {
     }
 
     pub fn find_active_by_user(user: Uuid) -> Select<Self> {
-        Self::find()
-            .filter(Column::UserId.eq(user))
-            .filter(Column::DeactivatedAt.is_null())
+        Self::find_by_user(user).filter(Column::DeactivatedAt.is_null())
     }
 }
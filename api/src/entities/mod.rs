@@ -1,9 +1,15 @@
 #![allow(clippy::wildcard_imports)] // for generated code
 
+pub mod api_credentials;
+pub mod credit_ledger;
 pub mod invites;
 pub mod members;
+pub mod organization_selections;
 pub mod organizations;
 pub mod owners;
+pub mod plans;
+pub mod processed_events;
+pub mod project_stats;
 pub mod projects;
 pub mod sea_orm_active_enums;
 
@@ -1,12 +1,21 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.5
 #![allow(clippy::all)]
 use async_graphql::*;
-use hub_core::{assets::AssetProxy, url::Url};
+use hub_core::assets::AssetProxy;
 use sea_orm::{entity::prelude::*, Condition, QueryOrder};
 use serde::{Deserialize, Serialize};
 
-use super::{invites, members, owners, projects, sea_orm_active_enums::InviteStatus, Project};
-use crate::AppContext;
+use super::{
+    invites, members, owners, plans, project_stats::OrganizationStats, projects,
+    sea_orm_active_enums::InviteStatus, Project,
+};
+use crate::{
+    credits,
+    errors::Result,
+    images::{self, ImageFormat},
+    quotas::{self, OrganizationQuotas},
+    AppContext,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "organizations")]
@@ -19,6 +28,15 @@ pub struct Model {
     pub deactivated_at: Option<DateTimeWithTimeZone>,
     #[sea_orm(column_type = "Text")]
     pub profile_image_url: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub website_url: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub twitter_handle: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub instagram_handle: Option<String>,
+    pub plan_id: Uuid,
 }
 
 /// A Holaplex organization is the top-level account within the Holaplex ecosystem. Each organization has a single owner who can invite members to join. Organizations use projects to organize NFT campaigns or initiatives.
@@ -35,11 +53,22 @@ pub struct Organization {
     pub deactivated_at: Option<DateTimeWithTimeZone>,
     /// The optional profile image associated with the Holaplex organization, which can be used to visually represent the organization.
     pub profile_image_url_original: Option<String>,
+    /// A short description of the Holaplex organization shown on its public page.
+    pub description: Option<String>,
+    /// The URL of the website of the Holaplex organization.
+    pub website_url: Option<String>,
+    /// The Twitter handle of the Holaplex organization, without the leading `@`.
+    pub twitter_handle: Option<String>,
+    /// The Instagram handle of the Holaplex organization, without the leading `@`.
+    pub instagram_handle: Option<String>,
+    /// The ID of the plan of the Holaplex organization.
+    pub plan_id: Uuid,
 }
 
 #[ComplexObject]
 impl Organization {
     /// The members who have been granted access to the Holaplex organization, represented by individuals who have been invited and accepted the invitation to join the organization.
+    #[graphql(complexity = "10 * child_complexity")]
     async fn members(&self, ctx: &Context<'_>) -> Result<Option<Vec<members::Member>>> {
         let AppContext { members_loader, .. } = ctx.data::<AppContext>()?;
         members_loader.load_one(self.id).await
@@ -52,6 +81,7 @@ impl Organization {
     }
 
     /// The invitations to join the Holaplex organization that have been sent to email addresses and are either awaiting or have been accepted by the recipients.
+    #[graphql(complexity = "10 * child_complexity")]
     async fn invites(
         &self,
         ctx: &Context<'_>,
@@ -78,6 +108,7 @@ impl Organization {
     }
 
     /// The projects that have been created and are currently associated with the Holaplex organization, which are used to organize NFT campaigns or initiatives within the organization.
+    #[graphql(complexity = "10 * child_complexity")]
     async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<Project>> {
         let AppContext { db, .. } = ctx.data::<AppContext>()?;
 
@@ -90,14 +121,72 @@ impl Organization {
             .map(|projects| projects.into_iter().map(|p| p.into()).collect())
     }
 
-    async fn profile_image_url(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+    /// The plan of the Holaplex organization, which sets the limits on its projects, members and pending invitations.
+    async fn plan(&self, ctx: &Context<'_>) -> Result<Option<plans::Model>> {
+        let AppContext { db, .. } = ctx.data::<AppContext>()?;
+
+        plans::Entity::find_by_id(self.plan_id)
+            .one(db.get())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// The current usage of the projects, members and pending invitations of the Holaplex organization compared to the limits of its plan.
+    async fn quotas(&self, ctx: &Context<'_>) -> Result<OrganizationQuotas> {
+        let AppContext { db, .. } = ctx.data::<AppContext>()?;
+
+        quotas::usage(db.get(), self.id).await
+    }
+
+    /// The usage statistics of the Holaplex organization, summed over all of its projects.
+    async fn stats(&self, ctx: &Context<'_>) -> Result<OrganizationStats> {
+        let AppContext {
+            organization_stats_loader,
+            ..
+        } = ctx.data::<AppContext>()?;
+
+        Ok(organization_stats_loader
+            .load_one(self.id)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// The credit balance of the Holaplex organization, computed from its credit ledger. Billed hub operations are deducted from the balance, which never goes below zero.
+    async fn credit_balance(&self, ctx: &Context<'_>) -> Result<i64> {
+        let AppContext { db, .. } = ctx.data::<AppContext>()?;
+
+        credits::balance(db.get(), self.id)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Look up a project of the Holaplex organization by its slug, this field returns `null` if no project with the slug exists in the organization.
+    async fn project(&self, ctx: &Context<'_>, slug: String) -> Result<Option<Project>> {
+        let AppContext { db, .. } = ctx.data::<AppContext>()?;
+
+        projects::Entity::find_by_slug(self.id, &slug)
+            .one(db.get())
+            .await
+            .map_err(Into::into)
+            .map(|project| project.map(Into::into))
+    }
+
+    /// The profile image of the Holaplex organization served through the asset proxy. The image can be resized with `width` and `height`, each between 1 and 2048 pixels, and converted to another `format`.
+    async fn profile_image_url(
+        &self,
+        ctx: &Context<'_>,
+        width: Option<u32>,
+        height: Option<u32>,
+        format: Option<ImageFormat>,
+    ) -> Result<Option<String>> {
         if let Some(image) = &self.profile_image_url_original {
             let asset_proxy = ctx.data::<AssetProxy>()?;
-            let url = Url::parse(&image)?;
-            asset_proxy
-                .proxy_ipfs_image(&url, None)
-                .map_err(|e| e.into())
-                .map(|u| u.map(Into::into))
+
+            images::proxy_url(
+                asset_proxy,
+                image,
+                images::transform_options(width, height, format)?,
+            )
         } else {
             Ok(None)
         }
@@ -112,6 +201,11 @@ impl From<Model> for Organization {
             created_at,
             deactivated_at,
             profile_image_url,
+            description,
+            website_url,
+            twitter_handle,
+            instagram_handle,
+            plan_id,
         }: Model,
     ) -> Self {
         Self {
@@ -120,6 +214,11 @@ impl From<Model> for Organization {
             created_at,
             deactivated_at,
             profile_image_url_original: profile_image_url,
+            description,
+            website_url,
+            twitter_handle,
+            instagram_handle,
+            plan_id,
         }
     }
 }
@@ -130,6 +229,28 @@ pub enum Relation {
     Members,
     #[sea_orm(has_one = "super::owners::Entity")]
     Owners,
+    #[sea_orm(has_many = "super::credit_ledger::Entity")]
+    CreditLedger,
+    #[sea_orm(
+        belongs_to = "super::plans::Entity",
+        from = "Column::PlanId",
+        to = "super::plans::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Plans,
+}
+
+impl Related<super::credit_ledger::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CreditLedger.def()
+    }
+}
+
+impl Related<super::plans::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Plans.def()
+    }
 }
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -4,7 +4,7 @@ use async_graphql::*;
 use sea_orm::entity::prelude::*;
 
 use super::organizations::Organization;
-use crate::AppContext;
+use crate::{errors::Result, users::UserProfile, AppContext};
 
 #[derive(Clone, Debug, Copy, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "owners")]
@@ -41,6 +41,16 @@ impl Owner {
 
         organization_loader.load_one(self.organization_id).await
     }
+
+    /// The profile of the user who owns the Holaplex organization, `null` when the profile cannot be found.
+    async fn user(&self, ctx: &Context<'_>) -> Result<Option<UserProfile>> {
+        let AppContext {
+            user_profile_loader,
+            ..
+        } = ctx.data::<AppContext>()?;
+
+        user_profile_loader.load_one(self.user_id).await
+    }
 }
 
 impl From<Model> for Owner {
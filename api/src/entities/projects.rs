@@ -1,11 +1,19 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.5
 
 use async_graphql::*;
-use hub_core::{assets::AssetProxy, url::Url};
-use sea_orm::entity::prelude::*;
+use hub_core::assets::AssetProxy;
+use sea_orm::{entity::prelude::*, QueryOrder};
 
-use super::organizations::Organization;
-use crate::AppContext;
+use super::{
+    api_credentials,
+    organizations::Organization,
+    project_stats::{self, ProjectStats},
+};
+use crate::{
+    errors::Result,
+    images::{self, ImageFormat},
+    AppContext,
+};
 
 /// A Holaplex project that belongs to an organization. Projects are used to group unique NFT campaigns or initiatives, and are used to assign objects that end customers will interact with, such as drops and wallets.
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -20,6 +28,15 @@ pub struct Model {
     pub deactivated_at: Option<DateTimeWithTimeZone>,
     #[sea_orm(column_type = "Text")]
     pub profile_image_url: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub website_url: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub twitter_handle: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub instagram_handle: Option<String>,
+    pub slug: String,
 }
 
 /// A Holaplex project that belongs to an organization. Projects are used to group unique NFT campaigns or initiatives, and are used to assign objects that end customers will interact with, such as drops and wallets.
@@ -38,6 +55,16 @@ pub struct Project {
     pub deactivated_at: Option<DateTimeWithTimeZone>,
     /// The optional profile image associated with the project, which can be used to visually represent the project.
     pub profile_image_url_original: Option<String>,
+    /// A short description of the project shown on its public page.
+    pub description: Option<String>,
+    /// The URL of the website of the project.
+    pub website_url: Option<String>,
+    /// The Twitter handle of the project, without the leading `@`.
+    pub twitter_handle: Option<String>,
+    /// The Instagram handle of the project, without the leading `@`.
+    pub instagram_handle: Option<String>,
+    /// The URL-safe identifier of the project, unique among the projects of its organization.
+    pub slug: String,
 }
 
 #[ComplexObject]
@@ -50,18 +77,55 @@ impl Project {
         organization_loader.load_one(self.organization_id).await
     }
 
-    async fn profile_image_url(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+    /// The profile image of the project served through the asset proxy. The image can be resized with `width` and `height`, each between 1 and 2048 pixels, and converted to another `format`.
+    async fn profile_image_url(
+        &self,
+        ctx: &Context<'_>,
+        width: Option<u32>,
+        height: Option<u32>,
+        format: Option<ImageFormat>,
+    ) -> Result<Option<String>> {
         if let Some(image) = &self.profile_image_url_original {
             let asset_proxy = ctx.data::<AssetProxy>()?;
-            let url = Url::parse(image)?;
-            asset_proxy
-                .proxy_ipfs_image(&url, None)
-                .map_err(|e| e.into())
-                .map(|u| u.map(Into::into))
+
+            images::proxy_url(
+                asset_proxy,
+                image,
+                images::transform_options(width, height, format)?,
+            )
         } else {
             Ok(None)
         }
     }
+
+    /// The usage statistics of the project, counting the drops, mints, customers and wallets created in it.
+    async fn stats(&self, ctx: &Context<'_>) -> Result<ProjectStats> {
+        let AppContext {
+            project_stats_loader,
+            ..
+        } = ctx.data::<AppContext>()?;
+
+        Ok(project_stats_loader
+            .load_one(self.id)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// The API credentials that grant machine-to-machine access to the project, including revoked credentials.
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn api_credentials(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Vec<api_credentials::ApiCredential>> {
+        let AppContext { db, .. } = ctx.data::<AppContext>()?;
+
+        api_credentials::Entity::find_by_project(self.id)
+            .order_by_desc(api_credentials::Column::CreatedAt)
+            .all(db.get())
+            .await
+            .map_err(Into::into)
+            .map(|credentials| credentials.into_iter().map(Into::into).collect())
+    }
 }
 
 impl From<Model> for Project {
@@ -73,6 +137,11 @@ impl From<Model> for Project {
             created_at,
             deactivated_at,
             profile_image_url,
+            description,
+            website_url,
+            twitter_handle,
+            instagram_handle,
+            slug,
         }: Model,
     ) -> Self {
         Self {
@@ -82,6 +151,11 @@ impl From<Model> for Project {
             created_at,
             deactivated_at,
             profile_image_url_original: profile_image_url,
+            description,
+            website_url,
+            twitter_handle,
+            instagram_handle,
+            slug,
         }
     }
 }
@@ -96,6 +170,10 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Organizations,
+    #[sea_orm(has_many = "super::api_credentials::Entity")]
+    ApiCredentials,
+    #[sea_orm(has_one = "super::project_stats::Entity")]
+    ProjectStats,
 }
 
 impl Related<super::organizations::Entity> for Entity {
@@ -104,10 +182,28 @@ impl Related<super::organizations::Entity> for Entity {
     }
 }
 
+impl Related<super::api_credentials::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiCredentials.def()
+    }
+}
+
+impl Related<super::project_stats::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProjectStats.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
 
 impl Entity {
     pub fn find_by_id(id: Uuid) -> Select<Self> {
         Self::find().filter(Column::Id.eq(id))
     }
+
+    pub fn find_by_slug(organization: Uuid, slug: &str) -> Select<Self> {
+        Self::find()
+            .filter(Column::OrganizationId.eq(organization))
+            .filter(Column::Slug.eq(slug))
+    }
 }
@@ -20,3 +20,20 @@ pub enum InviteStatus {
     #[sea_orm(string_value = "sent")]
     Sent,
 }
+
+/// The kind of an entry of the credit ledger of an organization.
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Enum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "credit_entry_kind")]
+pub enum CreditEntryKind {
+    /// Credits added to the balance of the organization.
+    #[sea_orm(string_value = "deposit")]
+    Deposit,
+    /// Credits consumed by a billed operation of the organization.
+    #[sea_orm(string_value = "deduction")]
+    Deduction,
+    /// A manual correction of the balance of the organization.
+    #[sea_orm(string_value = "adjustment")]
+    Adjustment,
+}
@@ -0,0 +1,164 @@
+//! Errors returned to GraphQL clients.
+//!
+//! Every error carries a stable code in `extensions.code` so clients can handle it without matching on the message. Errors raised by the database or other infrastructure are logged and reported to clients as `INTERNAL` without their details.
+
+use async_graphql::{Error, ErrorExtensions};
+use hub_core::prelude::*;
+use sea_orm::{DbErr, RuntimeErr};
+
+/// The SQLSTATE of a unique constraint violation in Postgres.
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// A result whose error is reported to GraphQL clients with a code.
+pub type Result<T, E = ApiError> = std::result::Result<T, E>;
+
+/// An error returned to GraphQL clients.
+///
+/// `ApiError` deliberately does not implement `Display`, so it is converted into an [`async_graphql::Error`] through its `From` implementation, which sets `extensions.code`, rather than through the blanket conversion of displayable errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// The requested record does not exist.
+    NotFound(String),
+    /// The caller is not authenticated.
+    Unauthorized(String),
+    /// The caller is authenticated but not allowed to perform the operation.
+    Forbidden(String),
+    /// The operation conflicts with the current state of a record.
+    Conflict(String),
+    /// The input of the operation is invalid.
+    Validation(String),
+    /// The operation would exceed a limit of the plan of the organization.
+    QuotaExceeded {
+        message: String,
+        resource: &'static str,
+        limit: i32,
+    },
+    /// The caller exceeded the rate limit of the operation.
+    RateLimited { message: String, retry_after: u64 },
+    /// An unexpected failure, the details of which are only logged.
+    Internal,
+}
+
+impl ApiError {
+    #[must_use]
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    #[must_use]
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    #[must_use]
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    #[must_use]
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    #[must_use]
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(message.into())
+    }
+
+    /// Logs an unexpected failure and returns an error that hides its details.
+    #[must_use]
+    pub fn internal(error: impl std::fmt::Display) -> Self {
+        error!("internal error: {error}");
+
+        Self::Internal
+    }
+
+    /// The error when the X-USER-ID header, or the verified identity replacing it, is missing.
+    #[must_use]
+    pub fn missing_user() -> Self {
+        Self::unauthorized("X-USER-ID header not found")
+    }
+
+    /// The code of the error, set in `extensions.code`.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Unauthorized(_) => "UNAUTHORIZED",
+            Self::Forbidden(_) => "FORBIDDEN",
+            Self::Conflict(_) => "CONFLICT",
+            Self::Validation(_) => "VALIDATION",
+            Self::QuotaExceeded { .. } => "QUOTA_EXCEEDED",
+            Self::RateLimited { .. } => "RATE_LIMITED",
+            Self::Internal => "INTERNAL",
+        }
+    }
+
+    /// The message of the error shown to clients.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::Conflict(message)
+            | Self::Validation(message)
+            | Self::QuotaExceeded { message, .. }
+            | Self::RateLimited { message, .. } => message,
+            Self::Internal => "internal server error",
+        }
+    }
+}
+
+impl From<ApiError> for Error {
+    fn from(value: ApiError) -> Self {
+        let code = value.code();
+
+        Error::new(value.message()).extend_with(|_, e| {
+            e.set("code", code);
+
+            match &value {
+                ApiError::QuotaExceeded {
+                    resource, limit, ..
+                } => {
+                    e.set("resource", *resource);
+                    e.set("limit", *limit);
+                },
+                ApiError::RateLimited { retry_after, .. } => e.set("retryAfter", *retry_after),
+                _ => (),
+            }
+        })
+    }
+}
+
+impl From<DbErr> for ApiError {
+    fn from(value: DbErr) -> Self {
+        Self::internal(value)
+    }
+}
+
+/// Whether a database error is a violation of the unique index or constraint named `constraint`, for callers that report it as a conflict rather than an internal error.
+#[must_use]
+pub fn is_unique_violation(error: &DbErr, constraint: &str) -> bool {
+    match error {
+        DbErr::Exec(RuntimeErr::SqlxError(e)) | DbErr::Query(RuntimeErr::SqlxError(e)) => {
+            e.as_database_error().map_or(false, |e| {
+                e.code().as_deref() == Some(UNIQUE_VIOLATION) && e.constraint() == Some(constraint)
+            })
+        },
+        _ => false,
+    }
+}
+
+/// Errors of async-graphql itself, such as context data missing from the schema.
+impl From<Error> for ApiError {
+    fn from(value: Error) -> Self {
+        Self::internal(value.message)
+    }
+}
+
+impl From<hub_core::anyhow::Error> for ApiError {
+    fn from(value: hub_core::anyhow::Error) -> Self {
+        Self::internal(value)
+    }
+}
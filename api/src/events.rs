@@ -0,0 +1,318 @@
+//! Processing of events consumed from other hub services.
+//!
+//! Consumed messages are first classified into [`ProjectActivity`] by [`classify`], which only inspects the decoded message and can be exercised with in-memory messages, before the activity is recorded in the project statistics and billed to the organization of the project by [`process`].
+
+use hub_core::{anyhow::Result, prelude::*, uuid::Uuid};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+
+use crate::{
+    credits::{self, CreditArgs, Deduction},
+    db::Connection,
+    entities::projects,
+    proto::{customer_events, nft_events, treasury_events},
+    Services,
+};
+
+/// An activity of another hub service that affects a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// A drop was created for the project.
+    DropCreated,
+    /// An edition of a drop of the project was minted.
+    DropMinted,
+    /// A customer was registered for the project.
+    CustomerCreated,
+    /// A wallet was created for a customer of the project.
+    WalletCreated,
+}
+
+impl Activity {
+    /// The `project_stats` column counting the activity.
+    fn stats_column(self) -> &'static str {
+        match self {
+            Self::DropCreated => "drops",
+            Self::DropMinted => "mints",
+            Self::CustomerCreated => "customers",
+            Self::WalletCreated => "wallets",
+        }
+    }
+
+    /// The name of the activity used in the references of credit ledger entries.
+    fn name(self) -> &'static str {
+        match self {
+            Self::DropCreated => "drop_created",
+            Self::DropMinted => "drop_minted",
+            Self::CustomerCreated => "customer_created",
+            Self::WalletCreated => "wallet_created",
+        }
+    }
+}
+
+/// An [`Activity`] attributed to the project it happened in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectActivity {
+    pub project_id: Uuid,
+    pub activity: Activity,
+    /// The ID of the event the activity was read from, as set in the key of the message.
+    pub event_id: String,
+}
+
+impl ProjectActivity {
+    /// The reference identifying the activity in the processed events and the credit ledger, so redelivered events are neither counted nor billed twice.
+    fn reference(&self) -> String {
+        format!("{}:{}", self.activity.name(), self.event_id)
+    }
+}
+
+/// Maps a consumed message to the project activity it represents. Returns `None` for events hub-orgs does not track or whose project ID is missing or malformed.
+#[must_use]
+pub fn classify(msg: &Services) -> Option<ProjectActivity> {
+    let (event_id, project_id, activity) = match msg {
+        Services::Nfts(key, e) => match &e.event {
+            Some(nft_events::Event::DropCreated(_)) => {
+                (&key.id, &key.project_id, Activity::DropCreated)
+            },
+            Some(nft_events::Event::DropMinted(_)) => {
+                (&key.id, &key.project_id, Activity::DropMinted)
+            },
+            _ => return None,
+        },
+        Services::Customers(key, e) => match &e.event {
+            Some(customer_events::Event::Created(customer)) => {
+                (&key.id, &customer.project_id, Activity::CustomerCreated)
+            },
+            _ => return None,
+        },
+        Services::Treasuries(key, e) => match &e.event {
+            Some(treasury_events::Event::CustomerWalletCreated(_)) => {
+                (&key.id, &key.project_id, Activity::WalletCreated)
+            },
+            _ => return None,
+        },
+    };
+
+    match Uuid::from_str(project_id) {
+        Ok(project_id) => Some(ProjectActivity {
+            project_id,
+            activity,
+            event_id: event_id.clone(),
+        }),
+        Err(e) => {
+            warn!("dropping {activity:?} event with invalid project id {project_id:?}: {e}");
+
+            None
+        },
+    }
+}
+
+/// Processes a message consumed from another hub service.
+///
+/// # Errors
+/// This function fails if the project statistics or the credit ledger cannot be updated.
+pub async fn process(msg: Services, db: Connection, credits: CreditArgs) -> Result<()> {
+    let Some(activity) = classify(&msg) else {
+        return Ok(());
+    };
+
+    record(db.get(), &activity).await?;
+    bill(db.get(), credits, &activity).await
+}
+
+/// Increments the project statistics counter of an activity. Activities of projects unknown to hub-orgs, and activities already counted, are ignored.
+///
+/// # Errors
+/// This function fails if the statistics cannot be updated.
+pub async fn record(conn: &DatabaseConnection, activity: &ProjectActivity) -> Result<()> {
+    let column = activity.activity.stats_column();
+
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            r#"WITH processed AS (
+                INSERT INTO processed_events (reference, project_id)
+                SELECT $1, id FROM projects WHERE id = $2
+                ON CONFLICT (reference) DO NOTHING
+                RETURNING project_id
+            )
+            INSERT INTO project_stats (project_id, {column})
+            SELECT project_id, 1 FROM processed
+            ON CONFLICT (project_id) DO UPDATE
+            SET {column} = project_stats.{column} + 1, updated_at = now();"#
+        ),
+        [activity.reference().into(), activity.project_id.into()],
+    );
+
+    conn.execute(stmt).await?;
+
+    Ok(())
+}
+
+/// Deducts the credits an activity costs from the organization of its project. Activities that are free, of unknown projects, or that were already billed are ignored, and deductions that would make the balance negative are refused.
+///
+/// # Errors
+/// This function fails if the project cannot be loaded or the credit ledger cannot be updated.
+pub async fn bill(
+    conn: &DatabaseConnection,
+    credits: CreditArgs,
+    project_activity: &ProjectActivity,
+) -> Result<()> {
+    let ProjectActivity {
+        project_id,
+        activity,
+        event_id,
+    } = project_activity;
+
+    let cost = credits.cost(*activity);
+
+    if cost <= 0 {
+        return Ok(());
+    }
+
+    let Some(project) = projects::Entity::find_by_id(*project_id).one(conn).await? else {
+        return Ok(());
+    };
+
+    let reference = project_activity.reference();
+
+    match credits::deduct(conn, project.organization_id, cost, reference).await? {
+        Deduction::Recorded(_) | Deduction::Duplicate => (),
+        Deduction::InsufficientBalance { balance } => {
+            warn!(
+                "organization {} has {balance} credits, {cost} needed to bill {activity:?} event {event_id}",
+                project.organization_id
+            );
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{
+        Customer, CustomerEventKey, CustomerEvents, NftEventKey, NftEvents, TreasuryEventKey,
+        TreasuryEvents,
+    };
+
+    const PROJECT_ID: &str = "f9e2b1a4-3c1d-4a8e-9b7f-2d6c5e4a3b21";
+
+    fn nft(event: nft_events::Event, project_id: &str) -> Services {
+        Services::Nfts(
+            NftEventKey {
+                id: "event-1".to_string(),
+                project_id: project_id.to_string(),
+                ..Default::default()
+            },
+            NftEvents { event: Some(event) },
+        )
+    }
+
+    #[test]
+    fn classifies_drop_created() {
+        let activity = classify(&nft(
+            nft_events::Event::DropCreated(Default::default()),
+            PROJECT_ID,
+        ));
+
+        assert_eq!(
+            activity,
+            Some(ProjectActivity {
+                project_id: Uuid::from_str(PROJECT_ID).unwrap(),
+                activity: Activity::DropCreated,
+                event_id: "event-1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn classifies_drop_minted() {
+        let activity = classify(&nft(
+            nft_events::Event::DropMinted(Default::default()),
+            PROJECT_ID,
+        ));
+
+        assert_eq!(activity.map(|a| a.activity), Some(Activity::DropMinted));
+    }
+
+    #[test]
+    fn classifies_customer_created_by_customer_project() {
+        let msg = Services::Customers(
+            CustomerEventKey {
+                id: "event-2".to_string(),
+                ..Default::default()
+            },
+            CustomerEvents {
+                event: Some(customer_events::Event::Created(Customer {
+                    project_id: PROJECT_ID.to_string(),
+                    ..Default::default()
+                })),
+            },
+        );
+
+        assert_eq!(
+            classify(&msg),
+            Some(ProjectActivity {
+                project_id: Uuid::from_str(PROJECT_ID).unwrap(),
+                activity: Activity::CustomerCreated,
+                event_id: "event-2".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn classifies_customer_wallet_created() {
+        let msg = Services::Treasuries(
+            TreasuryEventKey {
+                id: "event-3".to_string(),
+                project_id: PROJECT_ID.to_string(),
+                ..Default::default()
+            },
+            TreasuryEvents {
+                event: Some(treasury_events::Event::CustomerWalletCreated(
+                    Default::default(),
+                )),
+            },
+        );
+
+        assert_eq!(
+            classify(&msg).map(|a| a.activity),
+            Some(Activity::WalletCreated)
+        );
+    }
+
+    #[test]
+    fn ignores_untracked_events() {
+        let msg = Services::Treasuries(
+            TreasuryEventKey {
+                id: "event-4".to_string(),
+                project_id: PROJECT_ID.to_string(),
+                ..Default::default()
+            },
+            TreasuryEvents { event: None },
+        );
+
+        assert_eq!(classify(&msg), None);
+    }
+
+    #[test]
+    fn ignores_malformed_project_ids() {
+        let activity = classify(&nft(
+            nft_events::Event::DropCreated(Default::default()),
+            "not-a-uuid",
+        ));
+
+        assert_eq!(activity, None);
+    }
+
+    #[test]
+    fn references_activities_by_name_and_event_id() {
+        let activity = ProjectActivity {
+            project_id: Uuid::from_str(PROJECT_ID).unwrap(),
+            activity: Activity::WalletCreated,
+            event_id: "event-5".to_string(),
+        };
+
+        assert_eq!(activity.reference(), "wallet_created:event-5");
+    }
+}
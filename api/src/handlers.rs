@@ -5,23 +5,44 @@ use poem::{
     error::InternalServerError,
     handler,
     http::StatusCode,
-    web::{
-        cookie::{Cookie, CookieJar, SameSite},
-        Data, Html, Json, Path,
-    },
-    Error, IntoResponse, Result,
+    web::{cookie::CookieJar, Data, Html, Json, Path, RemoteAddr},
+    Error, IntoResponse, Response, Result,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    entities::{members, owners},
-    AppContext, AppState, UserEmail, UserID,
+    affiliations, credentials,
+    entities::organizations,
+    health::{Readiness, Status},
+    rate_limit, AppContext, AppState, UserEmail, UserID,
 };
 
-const HUB_ORG_COOKIE_NAME: &str = "_hub_org";
+/// Reports that the process is serving requests.
+#[handler]
+pub fn health_live() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Reports whether the service can handle requests, with the outcome of each of its checks. Responds with `503 Service Unavailable` when a check fails.
+#[handler]
+pub async fn health_ready(Data(state): Data<&AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.health_checker.readiness(&state.connection).await;
+
+    let status = match readiness.status {
+        Status::Ok => StatusCode::OK,
+        Status::Error => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(readiness))
+}
 
+/// Renders the Prometheus metrics of the service.
 #[handler]
-pub fn health() {}
+pub fn metrics_handler(Data(state): Data<&AppState>) -> Result<impl IntoResponse> {
+    let body = state.metrics.render(&state.connection)?;
+
+    Ok(body.with_content_type("text/plain; version=0.0.4"))
+}
 
 #[handler]
 pub fn playground() -> impl IntoResponse {
@@ -42,6 +63,8 @@ pub async fn graphql_handler(
         state.connection.clone(),
         user_id,
         user_email.map(|e| e.to_lowercase()),
+        state.user_directory.clone(),
+        &state.metrics,
     );
 
     Ok(state
@@ -50,7 +73,12 @@ pub async fn graphql_handler(
             req.0
                 .data(context)
                 .data(state.producer.clone())
-                .data(state.asset_proxy.clone()),
+                .data(state.asset_proxy.clone())
+                .data(state.image_uploader.clone())
+                .data(state.organization_cookie.clone())
+                .data(state.rate_limiter.clone())
+                .data(state.metrics.clone())
+                .data(state.health_checker.clone()),
         )
         .await
         .into())
@@ -73,40 +101,33 @@ pub async fn browser_login(
     let user_id = user_id
         .ok_or_else(|| Error::from_string("X-USER-ID not found", StatusCode::BAD_REQUEST))?;
 
-    let owners = owners::Entity::find_by_user(user_id)
-        .all(conn)
-        .await
-        .map_err(InternalServerError)?;
-
-    let memberships = members::Entity::find_by_user(user_id)
-        .all(conn)
+    let affiliations = affiliations::find_active(conn, user_id)
         .await
         .map_err(InternalServerError)?;
+    let organizations = affiliations.organization_ids();
 
-    let organizations: Vec<Uuid> = owners
-        .into_iter()
-        .map(|o| o.organization_id)
-        .chain(memberships.into_iter().map(|m| m.organization_id))
-        .collect();
-
-    match organizations.len() {
-        0 => Ok(Json(LoginCompleteResponse {
-            redirect_path: "/organizations/new".to_string(),
-        })),
-        1 => {
-            let mut cookie =
-                Cookie::new_with_str(HUB_ORG_COOKIE_NAME, organizations[0].to_string());
-            cookie.set_path("/");
-            cookie.set_http_only(Some(true));
-            cookie.set_same_site(Some(SameSite::Lax));
+    let selected = match organizations.as_slice() {
+        [] => {
+            return Ok(Json(LoginCompleteResponse {
+                redirect_path: "/organizations/new".to_string(),
+            }));
+        },
+        [organization] => Some(*organization),
+        _ => affiliations::last_selection(conn, user_id)
+            .await
+            .map_err(InternalServerError)?
+            .filter(|organization| affiliations.contains(*organization)),
+    };
 
-            cookie_jar.add(cookie);
+    match selected {
+        Some(organization) => {
+            state.organization_cookie.set(cookie_jar, organization);
 
             Ok(Json(LoginCompleteResponse {
                 redirect_path: "/projects".to_string(),
             }))
         },
-        _ => Ok(Json(LoginCompleteResponse {
+        None => Ok(Json(LoginCompleteResponse {
             redirect_path: "/organizations".to_string(),
         })),
     }
@@ -131,29 +152,16 @@ pub async fn browser_organization_select(
     let user_id = user_id
         .ok_or_else(|| Error::from_string("X-USER-ID not found", StatusCode::BAD_REQUEST))?;
 
-    let owners = owners::Entity::find_by_user(user_id)
-        .all(conn)
-        .await
-        .map_err(InternalServerError)?;
-
-    let memberships = members::Entity::find_active_by_user(user_id)
-        .all(conn)
+    let affiliations = affiliations::find_active(conn, user_id)
         .await
         .map_err(InternalServerError)?;
 
-    let organizations: Vec<Uuid> = owners
-        .into_iter()
-        .map(|o| o.organization_id)
-        .chain(memberships.into_iter().map(|m| m.organization_id))
-        .collect();
+    if affiliations.contains(organization) {
+        affiliations::remember_selection(conn, user_id, organization)
+            .await
+            .map_err(InternalServerError)?;
 
-    if organizations.contains(&organization) {
-        let mut cookie = Cookie::new_with_str(HUB_ORG_COOKIE_NAME, organization.to_string());
-        cookie.set_path("/");
-        cookie.set_http_only(Some(true));
-        cookie.set_same_site(Some(SameSite::Lax));
-
-        cookie_jar.add(cookie);
+        state.organization_cookie.set(cookie_jar, organization);
 
         Ok(Json(OrganizationSelectResponse {
             redirect_path: "/projects".to_string(),
@@ -165,3 +173,156 @@ pub async fn browser_organization_select(
         ))
     }
 }
+
+#[derive(Serialize)]
+pub struct LogoutResponse {
+    pub redirect_path: String,
+}
+
+/// Clears the organization selected in the browser when the user logs out. The selection is still restored the next time the user logs in.
+#[handler]
+pub fn browser_logout(state: Data<&AppState>, cookie_jar: &CookieJar) -> Json<LogoutResponse> {
+    let Data(state) = state;
+
+    state.organization_cookie.remove(cookie_jar);
+
+    Json(LogoutResponse {
+        redirect_path: "/login".to_string(),
+    })
+}
+
+#[derive(Serialize)]
+pub struct OrganizationDeselectResponse {
+    pub redirect_path: String,
+}
+
+/// Clears the organization selected in the browser and forgets it so it is not restored on the next login.
+#[handler]
+pub async fn browser_organization_deselect(
+    state: Data<&AppState>,
+    cookie_jar: &CookieJar,
+    user_id: UserID,
+) -> Result<Json<OrganizationDeselectResponse>> {
+    let Data(state) = state;
+    let UserID(user_id) = user_id;
+    let conn = state.connection.get();
+    let user_id = user_id
+        .ok_or_else(|| Error::from_string("X-USER-ID not found", StatusCode::BAD_REQUEST))?;
+
+    affiliations::forget_selection(conn, user_id)
+        .await
+        .map_err(InternalServerError)?;
+
+    state.organization_cookie.remove(cookie_jar);
+
+    Ok(Json(OrganizationDeselectResponse {
+        redirect_path: "/organizations".to_string(),
+    }))
+}
+
+#[derive(Serialize)]
+pub struct SelectedOrganization {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct SelectedOrganizationResponse {
+    pub organization: Option<SelectedOrganization>,
+}
+
+/// Responds with the organization selected in the browser. A selection the user is no longer affiliated with is cleared and reported as no selection.
+#[handler]
+pub async fn browser_organization(
+    state: Data<&AppState>,
+    cookie_jar: &CookieJar,
+    user_id: UserID,
+) -> Result<Json<SelectedOrganizationResponse>> {
+    let Data(state) = state;
+    let UserID(user_id) = user_id;
+    let conn = state.connection.get();
+    let user_id = user_id
+        .ok_or_else(|| Error::from_string("X-USER-ID not found", StatusCode::BAD_REQUEST))?;
+
+    let Some(organization) = state.organization_cookie.get(cookie_jar) else {
+        return Ok(Json(SelectedOrganizationResponse { organization: None }));
+    };
+
+    let affiliations = affiliations::find_active(conn, user_id)
+        .await
+        .map_err(InternalServerError)?;
+
+    if !affiliations.contains(organization) {
+        state.organization_cookie.remove(cookie_jar);
+
+        return Ok(Json(SelectedOrganizationResponse { organization: None }));
+    }
+
+    let organization = organizations::Entity::find_by_id(organization)
+        .one(conn)
+        .await
+        .map_err(InternalServerError)?
+        .map(|organization| SelectedOrganization {
+            id: organization.id,
+            name: organization.name,
+        });
+
+    Ok(Json(SelectedOrganizationResponse { organization }))
+}
+
+#[derive(Deserialize)]
+pub struct VerifyCredentialRequest {
+    pub token: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyCredentialResponse {
+    pub credential_id: Uuid,
+    pub project_id: Uuid,
+    pub organization_id: Uuid,
+}
+
+/// Verifies an API credential token on behalf of other hub services, responding with the project the credential grants access to. It is only served on the internal listener and is rate limited per client address.
+#[handler]
+pub async fn verify_credential(
+    state: Data<&AppState>,
+    remote_addr: &RemoteAddr,
+    body: Json<VerifyCredentialRequest>,
+) -> Result<Json<VerifyCredentialResponse>> {
+    let Data(state) = state;
+    let Json(VerifyCredentialRequest { token }) = body;
+    let conn = state.connection.get();
+
+    if let Some(address) = remote_addr.as_socket_addr() {
+        if let Some(retry_after) = state
+            .rate_limiter
+            .hit_client("verify_credential", address.ip())
+            .await
+        {
+            let retry_after = rate_limit::retry_after_secs(retry_after);
+
+            return Err(Error::from_response(
+                Response::builder()
+                    .status(StatusCode::TOO_MANY_REQUESTS)
+                    .header("Retry-After", retry_after)
+                    .body(format!(
+                        "too many verify_credential requests, retry in {retry_after} seconds"
+                    )),
+            ));
+        }
+    }
+
+    let credentials::Verified {
+        credential,
+        project,
+    } = credentials::verify(conn, &token)
+        .await
+        .map_err(InternalServerError)?
+        .ok_or_else(|| Error::from_string("invalid credential", StatusCode::UNAUTHORIZED))?;
+
+    Ok(Json(VerifyCredentialResponse {
+        credential_id: credential.id,
+        project_id: project.id,
+        organization_id: project.organization_id,
+    }))
+}
@@ -0,0 +1,250 @@
+//! Liveness and readiness checks.
+//!
+//! Liveness only tells the process is serving requests. Readiness also checks that the database is reachable and that its schema is up to date with the migrations the service was built with.
+//!
+//! The readiness response also reports the recent delivery health of the Kafka producer, which is whether it failed to deliver an event lately. It is not a check of the brokers and does not affect readiness, as the producer only learns of a broker outage when an event is sent.
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hub_core::{
+    clap,
+    tokio::{self, time::timeout},
+};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{prelude::*, DbBackend, FromQueryResult, Statement};
+use serde::Serialize;
+
+use crate::db::Connection;
+
+/// Arguments for configuring the readiness checks
+#[derive(Debug, Clone, Copy, clap::Args)]
+pub struct HealthArgs {
+    /// The time in seconds a failure of the producer to deliver an event is reported for.
+    #[arg(long, env, default_value_t = 30)]
+    pub health_kafka_delivery_window: u64,
+    /// The time in seconds each readiness check may take before it fails.
+    #[arg(long, env, default_value_t = 2)]
+    pub health_check_timeout: u64,
+}
+
+/// The outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Error,
+}
+
+/// The outcome of a check, with the reason it failed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn from_result(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self {
+                status: Status::Ok,
+                error: None,
+            },
+            Err(error) => Self {
+                status: Status::Error,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// The checks of the dependencies of the service.
+#[derive(Debug, Clone, Serialize)]
+pub struct Checks {
+    pub database: Check,
+    pub migrations: Check,
+}
+
+/// The readiness of the service, returned by `/health/ready`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub status: Status,
+    pub checks: Checks,
+    /// Whether the producer delivered the events it sent lately. It is informational only and does not affect `status`.
+    pub kafka_delivery: Check,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AppliedMigration {
+    version: String,
+}
+
+/// The last failure of the producer to deliver an event.
+#[derive(Debug)]
+struct DeliveryFailure {
+    at: Instant,
+    error: String,
+}
+
+/// Runs the readiness checks, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct HealthChecker {
+    timeout: Duration,
+    kafka_delivery_window: Duration,
+    delivery_failure: Arc<Mutex<Option<DeliveryFailure>>>,
+}
+
+impl HealthChecker {
+    #[must_use]
+    pub fn new(args: HealthArgs) -> Self {
+        let HealthArgs {
+            health_kafka_delivery_window,
+            health_check_timeout,
+        } = args;
+
+        Self {
+            timeout: Duration::from_secs(health_check_timeout),
+            kafka_delivery_window: Duration::from_secs(health_kafka_delivery_window),
+            delivery_failure: Arc::default(),
+        }
+    }
+
+    /// Records whether the producer delivered an event. A failure is reported until the delivery window elapses or an event is delivered.
+    pub fn observe_produce<E: Display>(&self, result: &Result<(), E>) {
+        let mut delivery_failure = self
+            .delivery_failure
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        *delivery_failure = result.as_ref().err().map(|e| DeliveryFailure {
+            at: Instant::now(),
+            error: e.to_string(),
+        });
+    }
+
+    /// Checks the database and migrations concurrently. The service is ready only if every check passes.
+    pub async fn readiness(&self, db: &Connection) -> Readiness {
+        let (database, migrations) =
+            tokio::join!(self.check(ping(db)), self.check(pending_migrations(db)));
+
+        let checks = Checks {
+            database,
+            migrations,
+        };
+
+        let status = if [&checks.database, &checks.migrations]
+            .iter()
+            .all(|check| check.status == Status::Ok)
+        {
+            Status::Ok
+        } else {
+            Status::Error
+        };
+
+        Readiness {
+            status,
+            checks,
+            kafka_delivery: Check::from_result(self.recent_delivery()),
+        }
+    }
+
+    async fn check(&self, check: impl std::future::Future<Output = Result<(), String>>) -> Check {
+        let result = timeout(self.timeout, check)
+            .await
+            .unwrap_or_else(|_| Err(format!("timed out after {:?}", self.timeout)));
+
+        Check::from_result(result)
+    }
+
+    fn recent_delivery(&self) -> Result<(), String> {
+        let delivery_failure = self
+            .delivery_failure
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        match &*delivery_failure {
+            Some(DeliveryFailure { at, error }) if at.elapsed() < self.kafka_delivery_window => {
+                Err(format!(
+                    "failed to deliver an event {}s ago: {error}",
+                    at.elapsed().as_secs()
+                ))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn ping(db: &Connection) -> Result<(), String> {
+    db.get()
+        .execute(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT 1".to_string(),
+        ))
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Compares the migrations applied to the database with the migrations the service was built with. The `seaql_migrations` table is read directly, as the migrator would create it when missing.
+async fn pending_migrations(db: &Connection) -> Result<(), String> {
+    let applied: HashSet<String> = AppliedMigration::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "SELECT version FROM seaql_migrations".to_string(),
+    ))
+    .all(db.get())
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .map(|m| m.version)
+    .collect();
+
+    let pending: Vec<String> = Migrator::migrations()
+        .iter()
+        .map(|m| m.name().to_string())
+        .filter(|name| !applied.contains(name))
+        .collect();
+
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("pending migrations: {}", pending.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker(health_kafka_delivery_window: u64) -> HealthChecker {
+        HealthChecker::new(HealthArgs {
+            health_kafka_delivery_window,
+            health_check_timeout: 2,
+        })
+    }
+
+    #[test]
+    fn producer_failures_are_reported_until_an_event_is_delivered() {
+        let checker = checker(30);
+
+        assert_eq!(checker.recent_delivery(), Ok(()));
+
+        checker.observe_produce(&Err::<(), _>("broker down"));
+        assert!(checker.recent_delivery().is_err());
+
+        checker.observe_produce(&Ok::<_, String>(()));
+        assert_eq!(checker.recent_delivery(), Ok(()));
+    }
+
+    #[test]
+    fn producer_failures_expire_after_the_delivery_window() {
+        let checker = checker(0);
+
+        checker.observe_produce(&Err::<(), _>("broker down"));
+        assert_eq!(checker.recent_delivery(), Ok(()));
+    }
+}
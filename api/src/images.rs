@@ -0,0 +1,235 @@
+//! Profile images attached to organizations and projects.
+
+use std::{io::Read, sync::Arc};
+
+use async_graphql::{Enum, UploadValue};
+use hub_core::{
+    assets::{self, AssetProxy, ImageOptions},
+    prelude::*,
+    tokio,
+    url::Url,
+    uuid::Uuid,
+};
+
+use crate::{
+    errors::{ApiError, Result},
+    storage::Storage,
+};
+
+/// The maximum length of a stored profile image URL.
+pub const MAX_URL_LENGTH: usize = 2048;
+
+const ALLOWED_SCHEMES: [&str; 2] = ["https", "ipfs"];
+
+/// The image formats accepted for uploads, as MIME type and file extension pairs.
+const ALLOWED_UPLOAD_TYPES: [(&str, &str); 4] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+];
+
+/// The largest width or height, in pixels, clients may request for a proxied profile image.
+pub const MAX_DIMENSION: u32 = 2048;
+
+/// The formats a proxied profile image can be converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum ImageFormat {
+    /// Convert the image to PNG.
+    Png,
+    /// Convert the image to JPEG.
+    Jpeg,
+    /// Convert the image to WebP.
+    Webp,
+}
+
+impl From<ImageFormat> for assets::ImageFormat {
+    fn from(format: ImageFormat) -> Self {
+        match format {
+            ImageFormat::Png => Self::Png,
+            ImageFormat::Jpeg => Self::Jpeg,
+            ImageFormat::Webp => Self::Webp,
+        }
+    }
+}
+
+/// Builds the asset proxy options for a requested transformation of a profile image. Returns `None` when no transformation is requested so the original image is served.
+///
+/// # Errors
+/// This function fails if the requested width or height is not between 1 and [`MAX_DIMENSION`] pixels.
+pub fn transform_options(
+    width: Option<u32>,
+    height: Option<u32>,
+    format: Option<ImageFormat>,
+) -> Result<Option<ImageOptions>> {
+    if width.is_none() && height.is_none() && format.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(ImageOptions {
+        width: validate_dimension("width", width)?,
+        height: validate_dimension("height", height)?,
+        format: format.map(Into::into),
+        ..Default::default()
+    }))
+}
+
+fn validate_dimension(name: &str, value: Option<u32>) -> Result<Option<u32>> {
+    match value {
+        Some(value) if !(1..=MAX_DIMENSION).contains(&value) => Err(ApiError::validation(format!(
+            "{name} must be between 1 and {MAX_DIMENSION} pixels"
+        ))),
+        _ => Ok(value),
+    }
+}
+
+/// Validates a profile image URL before it is stored, returning it in its normalized form.
+///
+/// # Errors
+/// This function fails if the URL is too long, cannot be parsed, does not use the `https` or `ipfs` scheme, or cannot be served through the asset proxy.
+pub fn validate_url(asset_proxy: &AssetProxy, url: &str) -> Result<String> {
+    if url.len() > MAX_URL_LENGTH {
+        return Err(ApiError::validation(format!(
+            "profile image URL must be at most {MAX_URL_LENGTH} characters"
+        )));
+    }
+
+    let parsed = Url::parse(url)
+        .map_err(|e| ApiError::validation(format!("invalid profile image URL: {e}")))?;
+
+    if !ALLOWED_SCHEMES.contains(&parsed.scheme()) {
+        return Err(ApiError::validation(
+            "profile image URL must use the https or ipfs scheme",
+        ));
+    }
+
+    asset_proxy
+        .proxy_ipfs_image(&parsed, None)
+        .map_err(|e| ApiError::validation(format!("unsupported profile image URL: {e}")))?;
+
+    Ok(parsed.into())
+}
+
+/// Validates an optional profile image URL submitted through a mutation input.
+///
+/// # Errors
+/// This function fails if the URL is present and rejected by [`validate_url`].
+pub fn validate_optional_url(
+    asset_proxy: &AssetProxy,
+    url: Option<String>,
+) -> Result<Option<String>> {
+    url.map(|url| validate_url(asset_proxy, &url)).transpose()
+}
+
+/// Resolves the URL clients should use to display a stored profile image.
+///
+/// Images stored before URLs were validated on write may not parse, these are logged and resolved as `null` instead of failing the whole field.
+///
+/// # Errors
+/// This function fails if the asset proxy is unable to build the proxied URL.
+pub fn proxy_url(
+    asset_proxy: &AssetProxy,
+    original: &str,
+    options: Option<ImageOptions>,
+) -> Result<Option<String>> {
+    let url = match Url::parse(original) {
+        Ok(url) => url,
+        Err(e) => {
+            warn!("unable to parse stored profile image URL {original:?}: {e}");
+
+            return Ok(None);
+        },
+    };
+
+    asset_proxy
+        .proxy_ipfs_image(&url, options)
+        .map_err(ApiError::internal)
+        .map(|u| u.map(Into::into))
+}
+
+/// Validates profile images uploaded through the GraphQL API and hands them to the configured [`Storage`].
+#[derive(Clone)]
+pub struct ImageUploader {
+    storage: Arc<dyn Storage>,
+    max_size: u64,
+}
+
+impl ImageUploader {
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>, max_size: u64) -> Self {
+        Self { storage, max_size }
+    }
+
+    /// Stores an uploaded image under `prefix` and returns its public URL.
+    ///
+    /// # Errors
+    /// This function fails if the file exceeds the maximum upload size, is not a PNG, JPEG, GIF or WebP image, or if the storage backend fails to persist it.
+    pub async fn upload(&self, upload: UploadValue, prefix: &str) -> Result<String> {
+        let content_type = upload.content_type.clone().unwrap_or_default();
+
+        let (_, extension) = ALLOWED_UPLOAD_TYPES
+            .iter()
+            .find(|(mime, _)| *mime == content_type)
+            .ok_or_else(|| {
+                ApiError::validation("profile image must be a PNG, JPEG, GIF or WebP image")
+            })?;
+
+        if upload.size().map_err(ApiError::internal)? > self.max_size {
+            return Err(self.too_large());
+        }
+
+        let max_size = self.max_size;
+        let content = tokio::task::spawn_blocking(move || {
+            let mut content = Vec::new();
+
+            upload
+                .into_read()
+                .take(max_size + 1)
+                .read_to_end(&mut content)
+                .map(|_| content)
+        })
+        .await
+        .map_err(ApiError::internal)?
+        .map_err(ApiError::internal)?;
+
+        if content.len() as u64 > self.max_size {
+            return Err(self.too_large());
+        }
+
+        if !matches_signature(&content_type, &content) {
+            return Err(ApiError::validation(
+                "profile image content does not match its declared type",
+            ));
+        }
+
+        let key = format!("{prefix}/{}.{extension}", Uuid::new_v4());
+
+        self.storage
+            .put(&key, &content_type, content)
+            .await
+            .map(Into::into)
+            .map_err(|e| {
+                error!("failed to store uploaded profile image: {e:?}");
+
+                ApiError::Internal
+            })
+    }
+
+    fn too_large(&self) -> ApiError {
+        ApiError::validation(format!(
+            "profile image must be at most {} bytes",
+            self.max_size
+        ))
+    }
+}
+
+/// Checks the leading bytes of an uploaded file against the signature of its declared MIME type.
+fn matches_signature(content_type: &str, content: &[u8]) -> bool {
+    match content_type {
+        "image/png" => content.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => content.starts_with(&[0xff, 0xd8, 0xff]),
+        "image/gif" => content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a"),
+        "image/webp" => content.starts_with(b"RIFF") && content.get(8..12) == Some(&b"WEBP"[..]),
+        _ => false,
+    }
+}
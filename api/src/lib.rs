@@ -2,45 +2,125 @@
 #![warn(clippy::pedantic, clippy::cargo)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod affiliations;
+pub mod auth;
+pub mod cookies;
+pub mod cors;
+pub mod credentials;
+pub mod credits;
 pub mod dataloaders;
 pub mod db;
 #[allow(clippy::pedantic)]
 pub mod entities;
+pub mod errors;
+pub mod events;
 pub mod handlers;
+pub mod health;
+pub mod images;
+pub mod metadata;
+pub mod metrics;
 pub mod mutations;
+pub mod persisted_queries;
 pub mod queries;
+pub mod quotas;
+pub mod rate_limit;
+pub mod slug;
+pub mod storage;
+pub mod telemetry;
+pub mod users;
+
+use std::{path::PathBuf, sync::Arc};
 
 use async_graphql::{
     dataloader::DataLoader,
-    extensions::{ApolloTracing, Logger},
+    extensions::{
+        apollo_persisted_queries::{ApolloPersistedQueries, LruCacheStorage},
+        ApolloTracing, Logger, OpenTelemetry,
+    },
+    futures_util::future::BoxFuture,
     EmptySubscription, Schema,
 };
+use cookies::OrganizationCookie;
 use dataloaders::{
-    InviteMemberLoader, MemberInviteLoader, MembersLoader, OrganizationLoader, OwnerLoader,
-    ProjectLoader,
+    InviteMemberLoader, MemberInviteLoader, MembersLoader, OrganizationLoader,
+    OrganizationStatsLoader, OwnerLoader, ProjectLoader, ProjectStatsLoader, UserProfileLoader,
 };
 use db::Connection;
+use health::HealthChecker;
 use hub_core::{
     anyhow::{Error, Result},
     assets::AssetProxy,
     clap,
+    consumer::RecvError,
     prelude::*,
     producer::Producer,
     tokio,
     uuid::Uuid,
 };
+use images::ImageUploader;
+use metrics::{GraphQLMetrics, InstrumentedLoader, Metrics};
 use mutations::Mutation;
+use opentelemetry::trace::FutureExt;
+use persisted_queries::TrustedDocuments;
 use poem::{async_trait, FromRequest, Request, RequestBody};
+use prost::Message;
 use queries::Query;
+use rate_limit::RateLimiter;
+use users::UserDirectory;
 
 pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/customer.proto.rs"));
+    include!(concat!(env!("OUT_DIR"), "/nfts.proto.rs"));
     include!(concat!(env!("OUT_DIR"), "/organization.proto.rs"));
+    include!(concat!(env!("OUT_DIR"), "/treasury.proto.rs"));
 }
 
 use proto::OrganizationEvents;
 
 impl hub_core::producer::Message for proto::OrganizationEvents {
-    type Key = proto::OrganizationEventKey;
+    type Key = telemetry::TracedEventKey;
+}
+
+/// The events of other hub services consumed by hub-orgs.
+#[derive(Debug)]
+pub enum Services {
+    Nfts(proto::NftEventKey, proto::NftEvents),
+    Customers(proto::CustomerEventKey, proto::CustomerEvents),
+    Treasuries(proto::TreasuryEventKey, proto::TreasuryEvents),
+}
+
+impl hub_core::consumer::MessageGroup for Services {
+    const REQUESTED_TOPICS: &'static [&'static str] =
+        &["hub-nfts", "hub-customers", "hub-treasuries"];
+
+    fn from_message<M: hub_core::consumer::Message>(msg: &M) -> Result<Self, RecvError> {
+        let topic = msg.topic();
+        let key = msg.key().ok_or(RecvError::MissingKey)?;
+        let val = msg.payload().ok_or(RecvError::MissingPayload)?;
+        debug!(topic, ?key);
+
+        match topic {
+            "hub-nfts" => {
+                let key = proto::NftEventKey::decode(key)?;
+                let val = proto::NftEvents::decode(val)?;
+
+                Ok(Services::Nfts(key, val))
+            },
+            "hub-customers" => {
+                let key = proto::CustomerEventKey::decode(key)?;
+                let val = proto::CustomerEvents::decode(val)?;
+
+                Ok(Services::Customers(key, val))
+            },
+            "hub-treasuries" => {
+                let key = proto::TreasuryEventKey::decode(key)?;
+                let val = proto::TreasuryEvents::decode(val)?;
+
+                Ok(Services::Treasuries(key, val))
+            },
+            t => Err(RecvError::BadTopic(t.into())),
+        }
+    }
 }
 
 #[derive(Debug, clap::Args)]
@@ -49,8 +129,63 @@ pub struct Args {
     #[arg(short, long, env, default_value_t = 3003)]
     pub port: u16,
 
+    /// The port of the listener serving the endpoints meant for other hub services only. It must not be exposed outside of the cluster.
+    #[arg(long, env, default_value_t = 3004)]
+    pub internal_port: u16,
+
+    /// The maximum number of consumed events processed at the same time. The consumer stops reading new messages while this many are in flight.
+    #[arg(long, env, default_value_t = 32)]
+    pub consumer_concurrency: usize,
+
     #[command(flatten)]
     pub db: db::DbArgs,
+
+    #[command(flatten)]
+    pub storage: storage::StorageArgs,
+
+    #[command(flatten)]
+    pub credits: credits::CreditArgs,
+
+    #[command(flatten)]
+    pub auth: auth::AuthArgs,
+
+    #[command(flatten)]
+    pub users: users::UserArgs,
+
+    #[command(flatten)]
+    pub cookie: cookies::CookieArgs,
+
+    #[command(flatten)]
+    pub cors: cors::CorsArgs,
+
+    #[command(flatten)]
+    pub schema: SchemaArgs,
+
+    #[command(flatten)]
+    pub rate_limit: rate_limit::RateLimitArgs,
+
+    #[command(flatten)]
+    pub health: health::HealthArgs,
+
+    #[command(flatten)]
+    pub telemetry: telemetry::TelemetryArgs,
+}
+
+/// Arguments for limiting the cost of GraphQL queries and caching their documents
+#[derive(Debug, Clone, clap::Args)]
+pub struct SchemaArgs {
+    /// The maximum nesting depth of a GraphQL query.
+    #[arg(long, env, default_value_t = 15)]
+    pub graphql_max_depth: usize,
+    /// The maximum complexity of a GraphQL query. Every field costs 1, and list fields multiply the cost of their selection by 10.
+    #[arg(long, env, default_value_t = 2000)]
+    pub graphql_max_complexity: usize,
+    /// The number of documents kept by automatic persisted queries. Set to 0 to disable automatic persisted queries.
+    #[arg(long, env, default_value_t = 1024)]
+    pub graphql_apq_cache_size: usize,
+    /// A JSON manifest mapping SHA-256 hashes to GraphQL documents. When set, only the documents of the manifest can be executed and automatic persisted queries are disabled.
+    #[arg(long, env)]
+    pub graphql_trusted_documents: Option<PathBuf>,
 }
 
 pub type AppSchema = Schema<Query, Mutation, EmptySubscription>;
@@ -72,12 +207,11 @@ impl TryFrom<&str> for UserID {
 impl<'a> FromRequest<'a> for UserID {
     async fn from_request(req: &'a Request, _body: &mut RequestBody) -> poem::Result<Self> {
         let id = req
-            .headers()
-            .get("X-USER-ID")
-            .and_then(|value| value.to_str().ok())
-            .map_or(Ok(Self(None)), Self::try_from)?;
+            .extensions()
+            .get::<auth::Identity>()
+            .and_then(|identity| identity.user_id);
 
-        Ok(id)
+        Ok(Self(id))
     }
 }
 
@@ -87,13 +221,12 @@ pub struct UserEmail(Option<String>);
 #[async_trait]
 impl<'a> FromRequest<'a> for UserEmail {
     async fn from_request(req: &'a Request, _body: &mut RequestBody) -> poem::Result<Self> {
-        let id = req
-            .headers()
-            .get("X-USER-EMAIL")
-            .and_then(|value| value.to_str().ok())
-            .map(std::string::ToString::to_string);
+        let email = req
+            .extensions()
+            .get::<auth::Identity>()
+            .and_then(|identity| identity.email.clone());
 
-        Ok(Self(id))
+        Ok(Self(email))
     }
 }
 
@@ -103,6 +236,12 @@ pub struct AppState {
     pub connection: Connection,
     pub producer: Producer<OrganizationEvents>,
     pub asset_proxy: AssetProxy,
+    pub image_uploader: ImageUploader,
+    pub user_directory: Arc<dyn UserDirectory>,
+    pub organization_cookie: OrganizationCookie,
+    pub rate_limiter: RateLimiter,
+    pub metrics: Metrics,
+    pub health_checker: HealthChecker,
 }
 
 impl AppState {
@@ -112,12 +251,24 @@ impl AppState {
         connection: Connection,
         producer: Producer<OrganizationEvents>,
         asset_proxy: AssetProxy,
+        image_uploader: ImageUploader,
+        user_directory: Arc<dyn UserDirectory>,
+        organization_cookie: OrganizationCookie,
+        rate_limiter: RateLimiter,
+        metrics: Metrics,
+        health_checker: HealthChecker,
     ) -> Self {
         Self {
             schema,
             connection,
             producer,
             asset_proxy,
+            image_uploader,
+            user_directory,
+            organization_cookie,
+            rate_limiter,
+            metrics,
+            health_checker,
         }
     }
 }
@@ -126,25 +277,69 @@ pub struct AppContext {
     pub db: Connection,
     pub user_id: Option<Uuid>,
     pub user_email: Option<String>,
-    pub organization_loader: DataLoader<OrganizationLoader>,
-    pub members_loader: DataLoader<MembersLoader>,
-    pub owner_loader: DataLoader<OwnerLoader>,
-    pub project_loader: DataLoader<ProjectLoader>,
-    pub member_invite_loader: DataLoader<MemberInviteLoader>,
-    pub invite_member_loader: DataLoader<InviteMemberLoader>,
+    pub organization_loader: DataLoader<InstrumentedLoader<OrganizationLoader>>,
+    pub members_loader: DataLoader<InstrumentedLoader<MembersLoader>>,
+    pub owner_loader: DataLoader<InstrumentedLoader<OwnerLoader>>,
+    pub project_loader: DataLoader<InstrumentedLoader<ProjectLoader>>,
+    pub member_invite_loader: DataLoader<InstrumentedLoader<MemberInviteLoader>>,
+    pub invite_member_loader: DataLoader<InstrumentedLoader<InviteMemberLoader>>,
+    pub project_stats_loader: DataLoader<InstrumentedLoader<ProjectStatsLoader>>,
+    pub organization_stats_loader: DataLoader<InstrumentedLoader<OrganizationStatsLoader>>,
+    pub user_profile_loader: DataLoader<InstrumentedLoader<UserProfileLoader>>,
+}
+
+/// Spawns a `DataLoader` batch in the trace context of the resolver that triggered it, so its queries are recorded in the trace of the request.
+fn spawn_batch(batch: BoxFuture<'static, ()>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(batch.with_current_context())
 }
 
 impl AppContext {
-    pub fn new(db: Connection, user_id: Option<Uuid>, user_email: Option<String>) -> Self {
-        let organization_loader =
-            DataLoader::new(OrganizationLoader::new(db.clone()), tokio::spawn);
-        let members_loader = DataLoader::new(MembersLoader::new(db.clone()), tokio::spawn);
-        let owner_loader = DataLoader::new(OwnerLoader::new(db.clone()), tokio::spawn);
-        let project_loader = DataLoader::new(ProjectLoader::new(db.clone()), tokio::spawn);
-        let member_invite_loader =
-            DataLoader::new(MemberInviteLoader::new(db.clone()), tokio::spawn);
-        let invite_member_loader =
-            DataLoader::new(InviteMemberLoader::new(db.clone()), tokio::spawn);
+    pub fn new(
+        db: Connection,
+        user_id: Option<Uuid>,
+        user_email: Option<String>,
+        user_directory: Arc<dyn UserDirectory>,
+        metrics: &Metrics,
+    ) -> Self {
+        let organization_loader = DataLoader::new(
+            metrics.instrument(OrganizationLoader::new(db.clone()), "organization"),
+            spawn_batch,
+        );
+        let members_loader = DataLoader::new(
+            metrics.instrument(MembersLoader::new(db.clone()), "members"),
+            spawn_batch,
+        );
+        let owner_loader = DataLoader::new(
+            metrics.instrument(OwnerLoader::new(db.clone()), "owner"),
+            spawn_batch,
+        );
+        let project_loader = DataLoader::new(
+            metrics.instrument(ProjectLoader::new(db.clone()), "project"),
+            spawn_batch,
+        );
+        let member_invite_loader = DataLoader::new(
+            metrics.instrument(MemberInviteLoader::new(db.clone()), "member_invite"),
+            spawn_batch,
+        );
+        let invite_member_loader = DataLoader::new(
+            metrics.instrument(InviteMemberLoader::new(db.clone()), "invite_member"),
+            spawn_batch,
+        );
+        let project_stats_loader = DataLoader::new(
+            metrics.instrument(ProjectStatsLoader::new(db.clone()), "project_stats"),
+            spawn_batch,
+        );
+        let organization_stats_loader = DataLoader::new(
+            metrics.instrument(
+                OrganizationStatsLoader::new(db.clone()),
+                "organization_stats",
+            ),
+            spawn_batch,
+        );
+        let user_profile_loader = DataLoader::new(
+            metrics.instrument(UserProfileLoader::new(user_directory), "user_profile"),
+            spawn_batch,
+        );
 
         Self {
             db,
@@ -156,16 +351,49 @@ impl AppContext {
             project_loader,
             member_invite_loader,
             invite_member_loader,
+            project_stats_loader,
+            organization_stats_loader,
+            user_profile_loader,
         }
     }
 }
 
-/// Builds the GraphQL Schema, attaching the Database to the context
-#[must_use]
-pub fn build_schema() -> AppSchema {
-    Schema::build(Query::default(), Mutation::default(), EmptySubscription)
+/// Builds the GraphQL Schema, attaching the Database to the context. Queries nested deeper or more complex than allowed by `args` are rejected before they are executed, every operation is recorded in `metrics`, and operations and resolvers are traced.
+///
+/// # Errors
+/// This function fails if the trusted documents manifest cannot be loaded.
+pub fn build_schema(args: SchemaArgs, metrics: Metrics) -> Result<AppSchema> {
+    let SchemaArgs {
+        graphql_max_depth,
+        graphql_max_complexity,
+        graphql_apq_cache_size,
+        graphql_trusted_documents,
+    } = args;
+
+    let trusted_documents = graphql_trusted_documents
+        .map(|path| TrustedDocuments::load(&path))
+        .transpose()?;
+    let operations = trusted_documents
+        .as_ref()
+        .map(TrustedDocuments::operation_names)
+        .unwrap_or_default();
+
+    let mut schema = Schema::build(Query::default(), Mutation::default(), EmptySubscription)
         .extension(ApolloTracing)
         .extension(Logger)
-        .enable_federation()
-        .finish()
+        .extension(GraphQLMetrics::new(metrics, operations))
+        .extension(OpenTelemetry::new(telemetry::tracer()))
+        .limit_depth(graphql_max_depth)
+        .limit_complexity(graphql_max_complexity)
+        .enable_federation();
+
+    if let Some(trusted_documents) = trusted_documents {
+        schema = schema.extension(trusted_documents);
+    } else if graphql_apq_cache_size > 0 {
+        schema = schema.extension(ApolloPersistedQueries::new(LruCacheStorage::new(
+            graphql_apq_cache_size,
+        )));
+    }
+
+    Ok(schema.finish())
 }
@@ -1,16 +1,39 @@
 //!
 
+use std::sync::Arc;
+
 use holaplex_hub_orgs::{
+    auth::{Authenticate, Authenticator},
     build_schema,
+    cookies::OrganizationCookie,
     db::Connection,
-    handlers::{browser_login, browser_organization_select, graphql_handler, health, playground},
-    proto, AppState, Args,
+    events,
+    handlers::{
+        browser_login, browser_logout, browser_organization, browser_organization_deselect,
+        browser_organization_select, graphql_handler, health_live, health_ready, metrics_handler,
+        playground, verify_credential,
+    },
+    health::HealthChecker,
+    images::ImageUploader,
+    metrics::{HttpMetrics, Metrics},
+    proto,
+    rate_limit::{MemoryBackend, RateLimiter},
+    storage::{LocalStorage, StorageArgs},
+    telemetry,
+    users::{EmptyDirectory, OryDirectory, UserArgs, UserDirectory},
+    AppState, Args, Services,
+};
+use hub_core::{
+    anyhow::Context as AnyhowContext,
+    futures_util::StreamExt,
+    prelude::*,
+    tokio::{self, sync::Semaphore},
 };
-use hub_core::anyhow::Context as AnyhowContext;
 use poem::{
+    endpoint::StaticFilesEndpoint,
     get,
     listener::TcpListener,
-    middleware::{AddData, CookieJarManager, Cors},
+    middleware::{AddData, CookieJarManager, OpenTelemetryTracing},
     post, EndpointExt, Route, Server,
 };
 
@@ -20,45 +43,173 @@ pub fn main() {
     };
 
     hub_core::run(opts, |common, args| {
-        let Args { port, db } = args;
+        let Args {
+            port,
+            internal_port,
+            consumer_concurrency,
+            db,
+            storage,
+            credits,
+            auth,
+            users,
+            cookie,
+            cors,
+            schema,
+            rate_limit,
+            health,
+            telemetry: telemetry_args,
+        } = args;
+        let StorageArgs {
+            upload_dir,
+            upload_base_url,
+            upload_max_size,
+        } = storage;
+
+        let UserArgs {
+            ory_admin_url,
+            ory_admin_token,
+        } = users;
+
+        let authenticator = Arc::new(Authenticator::new(auth)?);
+        let organization_cookie = OrganizationCookie::new(cookie)?;
+        let metrics = Metrics::new()?;
+        let health_checker = HealthChecker::new(health);
+        let rate_limiter = RateLimiter::new(rate_limit, Arc::new(MemoryBackend::default()));
+        let user_directory: Arc<dyn UserDirectory> = match ory_admin_url {
+            Some(url) => Arc::new(OryDirectory::new(&url, ory_admin_token)),
+            None => Arc::new(EmptyDirectory),
+        };
 
         common.rt.block_on(async move {
+            telemetry::init(telemetry_args)?;
+
             let connection = Connection::new(db)
                 .await
                 .context("failed to get database connection")?;
 
-            let schema = build_schema();
+            let schema = build_schema(schema, metrics.clone())?;
             let producer = common
                 .producer_cfg
                 .build::<proto::OrganizationEvents>()
                 .await?;
 
-            let state = AppState::new(schema, connection, producer, common.asset_proxy);
+            let consumer = common.consumer_cfg.build::<Services>().await?;
+            let consumer_connection = connection.clone();
+            let consumer_permits = Arc::new(Semaphore::new(consumer_concurrency.max(1)));
+
+            tokio::spawn(async move {
+                let mut stream = consumer.stream();
+
+                loop {
+                    let connection = consumer_connection.clone();
+                    let permit = match consumer_permits.clone().acquire_owned().await {
+                        Ok(permit) => permit,
+                        Err(e) => {
+                            error!("failed to wait for a free consumer slot: {e:?}");
+
+                            break;
+                        },
+                    };
+
+                    match stream.next().await {
+                        Some(Ok(msg)) => {
+                            tokio::spawn(async move {
+                                if let Err(e) = events::process(msg, connection, credits).await {
+                                    error!("failed to process message: {e:?}");
+                                }
+
+                                drop(permit);
+                            });
+                            tokio::task::yield_now().await;
+                        },
+                        Some(Err(e)) => warn!("failed to get message {:?}", e),
+                        None => {
+                            warn!("the event stream ended, no more messages will be consumed");
+
+                            break;
+                        },
+                    }
+                }
+            });
 
-            Server::new(TcpListener::bind(format!("0.0.0.0:{port}")))
-                .run(
+            let image_uploader = ImageUploader::new(
+                Arc::new(LocalStorage::new(upload_dir.clone(), upload_base_url)?),
+                upload_max_size,
+            );
+
+            let state = AppState::new(
+                schema,
+                connection,
+                producer,
+                common.asset_proxy,
+                image_uploader,
+                user_directory,
+                organization_cookie,
+                rate_limiter,
+                metrics.clone(),
+                health_checker,
+            );
+
+            let public = Route::new()
+                .at(
+                    "/graphql",
+                    post(graphql_handler)
+                        .with(AddData::new(state.clone()))
+                        .with(Authenticate::new(authenticator.clone()))
+                        .with(cors.policy())
+                        .with(HttpMetrics::new(metrics.clone(), "graphql"))
+                        .with(OpenTelemetryTracing::new(telemetry::tracer())),
+                )
+                .at("/playground", get(playground))
+                .nest("/uploads", StaticFilesEndpoint::new(upload_dir))
+                .nest(
+                    "/browser",
                     Route::new()
+                        .at("/login", post(browser_login))
+                        .at("/logout", post(browser_logout))
+                        .at("/organization", get(browser_organization))
                         .at(
-                            "/graphql",
-                            post(graphql_handler).with(AddData::new(state.clone())),
+                            "/organizations/deselect",
+                            post(browser_organization_deselect),
                         )
-                        .at("/playground", get(playground))
-                        .nest(
-                            "/browser",
-                            Route::new()
-                                .at("/login", post(browser_login))
-                                .at(
-                                    "/organizations/:organization/select",
-                                    post(browser_organization_select),
-                                )
-                                .with(AddData::new(state))
-                                .with(Cors::new().allow_credentials(true))
-                                .with(CookieJarManager::new()),
+                        .at(
+                            "/organizations/:organization/select",
+                            post(browser_organization_select),
                         )
-                        .at("/health", get(health)),
+                        .with(AddData::new(state.clone()))
+                        .with(Authenticate::new(authenticator))
+                        .with(cors.policy())
+                        .with(CookieJarManager::new())
+                        .with(HttpMetrics::new(metrics.clone(), "browser"))
+                        .with(OpenTelemetryTracing::new(telemetry::tracer())),
                 )
-                .await
-                .context("failed to build graphql server")
+                .at("/health", get(health_live))
+                .at("/health/live", get(health_live))
+                .at(
+                    "/health/ready",
+                    get(health_ready).with(AddData::new(state.clone())),
+                );
+
+            let internal = Route::new()
+                .at(
+                    "/credentials/verify",
+                    post(verify_credential)
+                        .with(AddData::new(state.clone()))
+                        .with(HttpMetrics::new(metrics, "credentials_verify"))
+                        .with(OpenTelemetryTracing::new(telemetry::tracer())),
+                )
+                .at("/metrics", get(metrics_handler).with(AddData::new(state)));
+
+            let res = tokio::try_join!(
+                Server::new(TcpListener::bind(format!("0.0.0.0:{port}"))).run(public),
+                Server::new(TcpListener::bind(format!("0.0.0.0:{internal_port}"))).run(internal),
+            )
+            .map(|_| ())
+            .context("failed to build graphql server");
+
+            telemetry::shutdown();
+
+            res
         })
     });
 }
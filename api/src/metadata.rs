@@ -0,0 +1,101 @@
+//! Public profile metadata shared by organizations and projects.
+
+use async_graphql::MaybeUndefined;
+use hub_core::url::Url;
+
+use crate::errors::{ApiError, Result};
+
+/// The maximum length of an organization or project description.
+pub const MAX_DESCRIPTION_LENGTH: usize = 1000;
+
+/// The maximum length of a website URL.
+pub const MAX_WEBSITE_URL_LENGTH: usize = 2048;
+
+/// The maximum length of a social handle, excluding the leading `@`.
+pub const MAX_HANDLE_LENGTH: usize = 30;
+
+/// Validates a description, returning it with surrounding whitespace removed.
+///
+/// # Errors
+/// This function fails if the description is longer than [`MAX_DESCRIPTION_LENGTH`] characters.
+pub fn validate_description(description: String) -> Result<String> {
+    let description = description.trim();
+
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+        return Err(ApiError::validation(format!(
+            "description must be at most {MAX_DESCRIPTION_LENGTH} characters"
+        )));
+    }
+
+    Ok(description.to_string())
+}
+
+/// Validates a website URL, returning it in its normalized form.
+///
+/// # Errors
+/// This function fails if the URL is too long, cannot be parsed or does not use the `http` or `https` scheme.
+pub fn validate_website_url(url: String) -> Result<String> {
+    if url.len() > MAX_WEBSITE_URL_LENGTH {
+        return Err(ApiError::validation(format!(
+            "website URL must be at most {MAX_WEBSITE_URL_LENGTH} characters"
+        )));
+    }
+
+    let parsed =
+        Url::parse(&url).map_err(|e| ApiError::validation(format!("invalid website URL: {e}")))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ApiError::validation(
+            "website URL must use the http or https scheme",
+        ));
+    }
+
+    Ok(parsed.into())
+}
+
+/// Validates a social handle, returning it without a leading `@`.
+///
+/// # Errors
+/// This function fails if the handle is empty, longer than [`MAX_HANDLE_LENGTH`] characters or contains characters other than ASCII letters, digits, `_` and `.`.
+pub fn validate_handle(handle: String) -> Result<String> {
+    let handle = handle.trim().trim_start_matches('@');
+
+    if handle.is_empty()
+        || handle.len() > MAX_HANDLE_LENGTH
+        || !handle
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.'))
+    {
+        return Err(ApiError::validation(format!(
+            "social handles must be 1 to {MAX_HANDLE_LENGTH} letters, digits, underscores or periods"
+        )));
+    }
+
+    Ok(handle.to_string())
+}
+
+/// Validates an optional metadata field submitted on creation.
+///
+/// # Errors
+/// This function fails if the value is present and rejected by `validate`.
+pub fn validate_optional(
+    value: Option<String>,
+    validate: fn(String) -> Result<String>,
+) -> Result<Option<String>> {
+    value.map(validate).transpose()
+}
+
+/// Validates an edit of an optional metadata field. Returns `None` when the field was omitted and should be left unchanged, and `Some(None)` when it was explicitly cleared.
+///
+/// # Errors
+/// This function fails if a value is provided and rejected by `validate`.
+pub fn validate_edit(
+    value: MaybeUndefined<String>,
+    validate: fn(String) -> Result<String>,
+) -> Result<Option<Option<String>>> {
+    match value {
+        MaybeUndefined::Undefined => Ok(None),
+        MaybeUndefined::Null => Ok(Some(None)),
+        MaybeUndefined::Value(value) => validate(value).map(|v| Some(Some(v))),
+    }
+}
@@ -0,0 +1,444 @@
+//! Prometheus metrics of the HTTP endpoints, GraphQL operations, `DataLoader` batches, database pool and Kafka producer, exposed on `/metrics` of the internal listener.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use async_graphql::{
+    dataloader::Loader as DataLoader,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery},
+    parser::types::{ExecutableDocument, OperationType},
+    Response, ServerResult, Variables,
+};
+use hub_core::anyhow::Result;
+use poem::{async_trait, Endpoint, IntoResponse, Middleware, Request};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::db::Connection;
+
+/// The prefix of every metric name.
+const NAMESPACE: &str = "hub_orgs";
+
+/// The buckets of the `DataLoader` batch size histogram.
+const BATCH_SIZE_BUCKETS: [f64; 10] =
+    [1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+/// The metrics collected by the service, cheap to clone.
+#[derive(Clone)]
+pub struct Metrics(Arc<Inner>);
+
+struct Inner {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    graphql_requests: IntCounterVec,
+    graphql_request_duration: HistogramVec,
+    dataloader_batch_size: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+    kafka_produced_messages: IntCounterVec,
+}
+
+impl Metrics {
+    /// Creates the metrics and registers them in a new registry.
+    ///
+    /// # Errors
+    /// This function fails if a metric is invalid or registered twice.
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["endpoint", "method", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time spent handling HTTP requests",
+            ),
+            &["endpoint", "method"],
+        )?;
+        let graphql_requests = IntCounterVec::new(
+            Opts::new("graphql_requests_total", "GraphQL operations executed"),
+            &["operation", "type", "status"],
+        )?;
+        let graphql_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "graphql_request_duration_seconds",
+                "Time spent executing GraphQL operations",
+            ),
+            &["operation", "type"],
+        )?;
+        let dataloader_batch_size = HistogramVec::new(
+            HistogramOpts::new(
+                "dataloader_batch_size",
+                "Number of keys loaded by a DataLoader batch",
+            )
+            .buckets(BATCH_SIZE_BUCKETS.to_vec()),
+            &["loader"],
+        )?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Connections of the database pool by state",
+            ),
+            &["state"],
+        )?;
+        let db_pool_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Maximum number of connections of the database pool",
+        )?;
+        let kafka_produced_messages = IntCounterVec::new(
+            Opts::new(
+                "kafka_produced_messages_total",
+                "Organization events sent to Kafka",
+            ),
+            &["outcome"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(graphql_requests.clone()))?;
+        registry.register(Box::new(graphql_request_duration.clone()))?;
+        registry.register(Box::new(dataloader_batch_size.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(db_pool_max_connections.clone()))?;
+        registry.register(Box::new(kafka_produced_messages.clone()))?;
+
+        Ok(Self(Arc::new(Inner {
+            registry,
+            http_requests,
+            http_request_duration,
+            graphql_requests,
+            graphql_request_duration,
+            dataloader_batch_size,
+            db_pool_connections,
+            db_pool_max_connections,
+            kafka_produced_messages,
+        })))
+    }
+
+    /// Wraps `loader` so the size of its batches is recorded under `name`.
+    #[must_use]
+    pub fn instrument<L>(&self, loader: L, name: &'static str) -> InstrumentedLoader<L> {
+        InstrumentedLoader {
+            loader,
+            name,
+            metrics: self.clone(),
+        }
+    }
+
+    /// Counts an organization event sent to Kafka.
+    pub fn observe_produce(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+
+        self.0
+            .kafka_produced_messages
+            .with_label_values(&[outcome])
+            .inc();
+    }
+
+    /// Renders every metric in the Prometheus text format, sampling the database pool first.
+    ///
+    /// # Errors
+    /// This function fails if the metrics cannot be encoded.
+    pub fn render(&self, db: &Connection) -> Result<String> {
+        let pool = db.pool_status();
+
+        self.0
+            .db_pool_connections
+            .with_label_values(&["active"])
+            .set(i64::from(pool.size.saturating_sub(pool.idle)));
+        self.0
+            .db_pool_connections
+            .with_label_values(&["idle"])
+            .set(i64::from(pool.idle));
+        self.0
+            .db_pool_max_connections
+            .set(i64::from(pool.max_connections));
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.0.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+/// The operation label of operations whose name is not known in advance.
+const OTHER_OPERATION: &str = "other";
+
+/// GraphQL extension counting and timing every operation by its type and name. Only the names of known operations, usually those of the trusted documents, are recorded, every other operation is recorded as `other` so clients cannot create new series.
+#[derive(Clone)]
+pub struct GraphQLMetrics {
+    metrics: Metrics,
+    operations: Arc<HashSet<String>>,
+}
+
+impl GraphQLMetrics {
+    #[must_use]
+    pub fn new(metrics: Metrics, operations: HashSet<String>) -> Self {
+        Self {
+            metrics,
+            operations: Arc::new(operations),
+        }
+    }
+}
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLMetricsExtension {
+            factory: self.clone(),
+            operations: Mutex::default(),
+        })
+    }
+}
+
+/// The [`GraphQLMetrics`] of a request, holding the operations of its document once parsed.
+struct GraphQLMetricsExtension {
+    factory: GraphQLMetrics,
+    operations: Mutex<Vec<(Option<String>, OperationType)>>,
+}
+
+impl GraphQLMetricsExtension {
+    /// The name and type labels of the executed operation.
+    fn labels(&self, operation_name: Option<&str>) -> (&str, &'static str) {
+        let operations = self
+            .operations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let operation = match (operation_name, operations.as_slice()) {
+            (Some(name), operations) => operations
+                .iter()
+                .find(|(operation, _)| operation.as_deref() == Some(name)),
+            (None, [operation]) => Some(operation),
+            (None, _) => None,
+        };
+
+        let Some((name, ty)) = operation else {
+            return (OTHER_OPERATION, "unknown");
+        };
+
+        let name = name
+            .as_deref()
+            .and_then(|name| self.factory.operations.get(name))
+            .map_or(OTHER_OPERATION, String::as_str);
+        let ty = match ty {
+            OperationType::Query => "query",
+            OperationType::Mutation => "mutation",
+            OperationType::Subscription => "subscription",
+        };
+
+        (name, ty)
+    }
+}
+
+#[async_trait]
+impl Extension for GraphQLMetricsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        *self
+            .operations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = document
+            .operations
+            .iter()
+            .map(|(name, operation)| (name.map(ToString::to_string), operation.node.ty))
+            .collect();
+
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let start = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+
+        let (operation, ty) = self.labels(operation_name);
+        let status = if response.is_ok() { "ok" } else { "error" };
+        let Metrics(inner) = &self.factory.metrics;
+
+        inner
+            .graphql_requests
+            .with_label_values(&[operation, ty, status])
+            .inc();
+        inner
+            .graphql_request_duration
+            .with_label_values(&[operation, ty])
+            .observe(start.elapsed().as_secs_f64());
+
+        response
+    }
+}
+
+/// Middleware counting and timing the requests of an endpoint, labeled with a fixed endpoint name so path parameters do not create new series.
+#[derive(Clone)]
+pub struct HttpMetrics {
+    metrics: Metrics,
+    endpoint: &'static str,
+}
+
+impl HttpMetrics {
+    #[must_use]
+    pub fn new(metrics: Metrics, endpoint: &'static str) -> Self {
+        Self { metrics, endpoint }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for HttpMetrics {
+    type Output = HttpMetricsEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        HttpMetricsEndpoint {
+            ep,
+            metrics: self.metrics.clone(),
+            endpoint: self.endpoint,
+        }
+    }
+}
+
+/// The endpoint wrapped by [`HttpMetrics`].
+pub struct HttpMetricsEndpoint<E> {
+    ep: E,
+    metrics: Metrics,
+    endpoint: &'static str,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for HttpMetricsEndpoint<E> {
+    type Output = poem::Response;
+
+    async fn call(&self, req: Request) -> poem::Result<Self::Output> {
+        let method = req.method().to_string();
+        let start = Instant::now();
+
+        let res = self.ep.call(req).await.map(IntoResponse::into_response);
+
+        let status = match &res {
+            Ok(res) => res.status(),
+            Err(e) => e.status(),
+        };
+        let Metrics(inner) = &self.metrics;
+
+        inner
+            .http_requests
+            .with_label_values(&[self.endpoint, &method, status.as_str()])
+            .inc();
+        inner
+            .http_request_duration
+            .with_label_values(&[self.endpoint, &method])
+            .observe(start.elapsed().as_secs_f64());
+
+        res
+    }
+}
+
+/// A `DataLoader` recording the size of every batch it loads, built with [`Metrics::instrument`].
+pub struct InstrumentedLoader<L> {
+    loader: L,
+    name: &'static str,
+    metrics: Metrics,
+}
+
+#[async_trait]
+impl<K, L> DataLoader<K> for InstrumentedLoader<L>
+where
+    K: Send + Sync + Hash + Eq + Clone + 'static,
+    L: DataLoader<K>,
+{
+    type Error = L::Error;
+    type Value = L::Value;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, Self::Value>, Self::Error> {
+        #[allow(clippy::cast_precision_loss)]
+        let size = keys.len() as f64;
+
+        self.metrics
+            .0
+            .dataloader_batch_size
+            .with_label_values(&[self.name])
+            .observe(size);
+
+        self.loader.load(keys).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+
+    use super::*;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn value(&self) -> i32 {
+            1
+        }
+    }
+
+    #[async_std::test]
+    async fn labels_only_known_operation_names() {
+        let metrics = Metrics::new().unwrap();
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(GraphQLMetrics::new(
+                metrics.clone(),
+                HashSet::from(["Known".to_string()]),
+            ))
+            .finish();
+
+        schema.execute("query Known { value }").await;
+        schema.execute("query Unknown { value }").await;
+        schema.execute("{ value }").await;
+        schema
+            .execute(
+                Request::new("query Unknown { value } query Known { value }")
+                    .operation_name("Known"),
+            )
+            .await;
+
+        let families = metrics.0.registry.gather();
+        let requests = families
+            .iter()
+            .find(|family| family.get_name() == "hub_orgs_graphql_requests_total")
+            .unwrap();
+        let mut series: Vec<_> = requests
+            .get_metric()
+            .iter()
+            .map(|metric| {
+                let labels: Vec<_> = metric
+                    .get_label()
+                    .iter()
+                    .map(|label| label.get_value().to_string())
+                    .collect();
+
+                (labels, metric.get_counter().get_value())
+            })
+            .collect();
+        series.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // labels are sorted by name: operation, status, type
+        assert_eq!(series, vec![
+            (vec!["Known".into(), "ok".into(), "query".into()], 2.0),
+            (vec!["other".into(), "ok".into(), "query".into()], 2.0),
+        ]);
+    }
+}
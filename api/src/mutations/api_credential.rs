@@ -0,0 +1,160 @@
+use async_graphql::{Context, InputObject, Object, SimpleObject};
+use hub_core::chrono::Utc;
+use sea_orm::{prelude::*, Set};
+
+use crate::{
+    affiliations, credentials,
+    entities::{
+        api_credentials::{self, ApiCredential},
+        projects,
+    },
+    errors::{ApiError, Result},
+    rate_limit, AppContext,
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mutation;
+
+#[Object(name = "ApiCredentialMutation")]
+impl Mutation {
+    /// This mutation creates an API credential for the project. The secret token is only included in the response of this mutation and cannot be retrieved afterwards.
+    ///
+    /// # Errors
+    /// This mutation produces an error if it is unable to connect to the database, if the project does not exist or is deactivated, if the user is not set in the X-USER-ID header or is not an active owner or member of the organization of the project, or if the rate limit of credential creation has been exceeded.
+    pub async fn create_api_credential(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateApiCredentialInput,
+    ) -> Result<CreateApiCredentialPayload> {
+        let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
+        let conn = db.get();
+
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        let project = projects::Entity::find_by_id(input.project)
+            .one(conn)
+            .await?
+            .ok_or_else(|| ApiError::not_found("project not found"))?;
+
+        if project.deactivated_at.is_some() {
+            return Err(ApiError::conflict("project is deactivated"));
+        }
+
+        authorize(conn, user_id, project.organization_id).await?;
+
+        rate_limit::check(
+            ctx,
+            "create_api_credential",
+            user_id,
+            Some(project.organization_id),
+        )
+        .await?;
+
+        let credentials::GeneratedToken {
+            token,
+            prefix,
+            hash,
+        } = credentials::generate();
+
+        let credential = api_credentials::ActiveModel {
+            project_id: Set(project.id),
+            name: Set(input.name),
+            token_prefix: Set(prefix),
+            secret_hash: Set(hash),
+            created_by: Set(user_id),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        Ok(CreateApiCredentialPayload {
+            credential: credential.into(),
+            token,
+        })
+    }
+
+    /// This mutation revokes an API credential. Revoked credentials can no longer be used to authenticate, revoking an already revoked credential has no effect.
+    ///
+    /// # Errors
+    /// This mutation produces an error if it is unable to connect to the database, if the credential does not exist, or if the user is not set in the X-USER-ID header or is not an active owner or member of the organization of the project.
+    pub async fn revoke_api_credential(
+        &self,
+        ctx: &Context<'_>,
+        input: RevokeApiCredentialInput,
+    ) -> Result<RevokeApiCredentialPayload> {
+        let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
+        let conn = db.get();
+
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        let (credential, project) = api_credentials::Entity::find_by_id(input.id)
+            .find_also_related(projects::Entity)
+            .one(conn)
+            .await?
+            .ok_or_else(|| ApiError::not_found("api credential not found"))?;
+        let project = project.ok_or_else(|| ApiError::not_found("project not found"))?;
+
+        authorize(conn, user_id, project.organization_id).await?;
+
+        if credential.revoked_at.is_some() {
+            return Ok(RevokeApiCredentialPayload {
+                credential: credential.into(),
+            });
+        }
+
+        let mut active_model: api_credentials::ActiveModel = credential.into();
+        active_model.revoked_at = Set(Some(Utc::now().into()));
+
+        let credential = active_model.update(conn).await?;
+
+        Ok(RevokeApiCredentialPayload {
+            credential: credential.into(),
+        })
+    }
+}
+
+/// The input used for creating an API credential.
+#[derive(Debug, InputObject)]
+pub struct CreateApiCredentialInput {
+    /// The ID of the project the API credential grants access to.
+    pub project: Uuid,
+    /// The friendly name of the API credential.
+    #[graphql(validator(min_length = 1, max_length = 100))]
+    pub name: String,
+}
+
+/// The payload returned by the `createApiCredential` mutation.
+#[derive(Debug, SimpleObject)]
+pub struct CreateApiCredentialPayload {
+    /// The API credential that was created.
+    pub credential: ApiCredential,
+    /// The secret token of the API credential. Store it securely, it is not possible to retrieve it again.
+    pub token: String,
+}
+
+/// The input used for revoking an API credential.
+#[derive(Debug, InputObject)]
+pub struct RevokeApiCredentialInput {
+    /// The ID of the API credential.
+    pub id: Uuid,
+}
+
+/// The payload returned by the `revokeApiCredential` mutation.
+#[derive(Debug, SimpleObject)]
+pub struct RevokeApiCredentialPayload {
+    /// The API credential that was revoked.
+    pub credential: ApiCredential,
+}
+
+/// Checks that the user is an active owner or member of the organization whose credentials are managed.
+async fn authorize(conn: &DatabaseConnection, user: Uuid, organization: Uuid) -> Result<()> {
+    let affiliations = affiliations::find_active(conn, user).await?;
+
+    if affiliations.contains(organization) {
+        Ok(())
+    } else {
+        Err(ApiError::forbidden(
+            "user not affiliated to the organization",
+        ))
+    }
+}
@@ -0,0 +1,87 @@
+use async_graphql::{Context, InputObject, Object, SimpleObject};
+use sea_orm::prelude::*;
+
+use crate::{
+    credits,
+    entities::{credit_ledger, organizations, owners},
+    errors::{self, ApiError, Result},
+    AppContext,
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mutation;
+
+#[Object(name = "CreditMutation")]
+impl Mutation {
+    /// This mutation deposits credits into the credit ledger of an organization. Only the owner of the organization can deposit credits.
+    ///
+    /// # Errors
+    /// This mutation produces an error if it is unable to connect to the database, if the organization does not exist or is deactivated, if the user is not set in the X-USER-ID header or is not the owner of the organization, or if the reference was already used for the organization.
+    pub async fn deposit_credits(
+        &self,
+        ctx: &Context<'_>,
+        input: DepositCreditsInput,
+    ) -> Result<DepositCreditsPayload> {
+        let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
+        let conn = db.get();
+
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        let organization = organizations::Entity::find_by_id(input.organization)
+            .one(conn)
+            .await?
+            .ok_or_else(|| ApiError::not_found("organization not found"))?;
+
+        if organization.deactivated_at.is_some() {
+            return Err(ApiError::conflict("organization is deactivated"));
+        }
+
+        owners::Entity::find()
+            .filter(owners::Column::OrganizationId.eq(organization.id))
+            .filter(owners::Column::UserId.eq(user_id))
+            .one(conn)
+            .await?
+            .ok_or_else(|| {
+                ApiError::forbidden("only the owner of the organization can deposit credits")
+            })?;
+
+        let reference = input
+            .reference
+            .unwrap_or_else(|| format!("deposit:{}", Uuid::new_v4()));
+
+        let entry = credits::deposit(conn, organization.id, input.amount, reference, user_id)
+            .await
+            .map_err(|e| {
+                if errors::is_unique_violation(&e, credits::REFERENCE_INDEX) {
+                    ApiError::conflict("reference already used for the organization")
+                } else {
+                    e.into()
+                }
+            })?;
+        let balance = credits::balance(conn, organization.id).await?;
+
+        Ok(DepositCreditsPayload { entry, balance })
+    }
+}
+
+/// The input used for depositing credits into an organization.
+#[derive(Debug, InputObject)]
+pub struct DepositCreditsInput {
+    /// The ID of the organization receiving the credits.
+    pub organization: Uuid,
+    /// The amount of credits to deposit.
+    #[graphql(validator(minimum = 1))]
+    pub amount: i64,
+    /// The reference of the deposit, for example the ID of the payment that funded it. A reference is generated when omitted.
+    #[graphql(validator(min_length = 1, max_length = 255))]
+    pub reference: Option<String>,
+}
+
+/// The payload returned by the `depositCredits` mutation.
+#[derive(Debug, SimpleObject)]
+pub struct DepositCreditsPayload {
+    /// The ledger entry recording the deposit.
+    pub entry: credit_ledger::Model,
+    /// The credit balance of the organization after the deposit.
+    pub balance: i64,
+}
@@ -1,11 +1,13 @@
-use async_graphql::{Context, Error, InputObject, Object, Result, SimpleObject};
-use hub_core::{chrono::Utc, producer::Producer};
-use sea_orm::{prelude::*, Set};
+use async_graphql::{Context, InputObject, Object, SimpleObject};
+use hub_core::chrono::Utc;
+use sea_orm::{prelude::*, Set, TransactionTrait};
 
 use crate::{
     entities::{invites, members, organizations, sea_orm_active_enums::InviteStatus},
+    errors::{ApiError, Result},
     proto::{organization_events::Event, Invite, Member, OrganizationEventKey, OrganizationEvents},
-    AppContext,
+    quotas::{self, Resource},
+    rate_limit, AppContext,
 };
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -15,16 +17,17 @@ pub struct Mutation;
 impl Mutation {
     /// To invite a person to the organization, provide their email address.
     /// # Error
-    /// This mutation will produce an error if it is unable to connect to the database or if there is no associated user set in the X-USER-ID header.
+    /// This mutation will produce an error if it is unable to connect to the database, if there is no associated user set in the X-USER-ID header, if the organization has reached the pending invitation limit of its plan, or if the rate limit of invitations has been exceeded.
     pub async fn invite_member(
         &self,
         ctx: &Context<'_>,
         input: MemberInput,
     ) -> Result<invites::Model> {
         let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
-        let producer = ctx.data::<Producer<OrganizationEvents>>()?;
 
-        let user_id = user_id.ok_or_else(|| Error::new("X-USER-ID header not found"))?;
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        rate_limit::check(ctx, "invite_member", user_id, Some(input.organization)).await?;
 
         let invite = invites::Entity::find()
             .filter(invites::Column::Email.eq(input.email.clone()))
@@ -33,13 +36,17 @@ impl Mutation {
             .await?;
 
         if invite.is_some() {
-            return Err(Error::new("Invite already exists"));
+            return Err(ApiError::conflict("Invite already exists"));
         }
 
         let organization = organizations::Entity::find_by_id(input.organization)
             .one(db.get())
             .await?
-            .ok_or_else(|| Error::new("organization not found"))?;
+            .ok_or_else(|| ApiError::not_found("organization not found"))?;
+
+        let txn = db.get().begin().await?;
+
+        quotas::check(&txn, organization.id, Resource::PendingInvites).await?;
 
         let active_model = invites::ActiveModel {
             organization_id: Set(input.organization),
@@ -49,7 +56,9 @@ impl Mutation {
             ..Default::default()
         };
 
-        let invite = active_model.insert(db.get()).await?;
+        let invite = active_model.insert(&txn).await?;
+
+        txn.commit().await?;
 
         let event = OrganizationEvents {
             event: Some(Event::InviteCreated(Invite {
@@ -63,14 +72,14 @@ impl Mutation {
             user_id: user_id.to_string(),
         };
 
-        producer.send(Some(&event), Some(&key)).await?;
+        super::emit(ctx, &event, &key).await?;
 
         Ok(invite)
     }
 
     /// Accept an invite to the organization.
     /// # Error
-    /// This mutation will produce an error if it is unable to connect to the database or if the user's email does not match the invitation.
+    /// This mutation will produce an error if it is unable to connect to the database, if the user's email does not match the invitation, or if the organization has reached the member limit of its plan.
     pub async fn accept_invite(
         &self,
         ctx: &Context<'_>,
@@ -83,27 +92,30 @@ impl Mutation {
             ..
         } = ctx.data::<AppContext>()?;
         let conn = db.get();
-        let producer = ctx.data::<Producer<OrganizationEvents>>()?;
 
-        let user_id = user_id.ok_or_else(|| Error::new("X-USER-ID header not found"))?;
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
         let user_email = user_email
             .clone()
-            .ok_or_else(|| Error::new("X-EMAIL-ID header not found"))?;
+            .ok_or_else(|| ApiError::unauthorized("X-EMAIL-ID header not found"))?;
 
         let invite = invites::Entity::find()
             .filter(invites::Column::Id.eq(input.invite))
             .one(conn)
             .await?
-            .ok_or_else(|| Error::new("invite not found"))?;
+            .ok_or_else(|| ApiError::not_found("invite not found"))?;
 
         validate_email_match(&(invite.email.to_lowercase(), user_email))?;
 
+        let txn = conn.begin().await?;
+
+        quotas::check(&txn, invite.organization_id, Resource::Members).await?;
+
         let mut active_model: invites::ActiveModel = invite.into();
 
         active_model.status = Set(InviteStatus::Accepted);
         active_model.updated_at = Set(Some(Utc::now().into()));
 
-        let invite = active_model.update(conn).await?;
+        let invite = active_model.update(&txn).await?;
 
         let member = members::ActiveModel {
             user_id: Set(user_id),
@@ -112,7 +124,9 @@ impl Mutation {
             ..Default::default()
         };
 
-        let member_model = member.insert(conn).await?;
+        let member_model = member.insert(&txn).await?;
+
+        txn.commit().await?;
 
         let event = OrganizationEvents {
             event: Some(Event::MemberAdded(Member {
@@ -125,7 +139,7 @@ impl Mutation {
             user_id: user_id.to_string(),
         };
 
-        producer.send(Some(&event), Some(&key)).await?;
+        super::emit(ctx, &event, &key).await?;
 
         Ok(AcceptInvitePayload { invite })
     }
@@ -160,5 +174,5 @@ fn validate_email_match(emails: &(String, String)) -> Result<()> {
     if emails.0 == emails.1 {
         return Ok(());
     }
-    Err(Error::new("user email does not match the invite"))
+    Err(ApiError::forbidden("user email does not match the invite"))
 }
@@ -1,11 +1,13 @@
 // Import necessary dependencies
-use async_graphql::{Context, Error, InputObject, Object, Result};
-use hub_core::{chrono::Utc, producer::Producer};
-use sea_orm::{prelude::*, Set};
+use async_graphql::{Context, InputObject, Object};
+use hub_core::chrono::Utc;
+use sea_orm::{prelude::*, Set, TransactionTrait};
 
 use crate::{
     entities::members::{self, Member},
+    errors::{ApiError, Result},
     proto::{self, organization_events::Event, OrganizationEventKey, OrganizationEvents},
+    quotas::{self, Resource},
     AppContext,
 };
 
@@ -28,13 +30,12 @@ impl Mutation {
     ) -> Result<Member> {
         // Get AppContext and Producer instances from the Context object
         let AppContext { db, .. } = ctx.data::<AppContext>()?;
-        let producer = ctx.data::<Producer<OrganizationEvents>>()?;
 
         // Find a member by ID
         let member = members::Entity::find_by_id(input.id)
             .one(db.get())
             .await?
-            .ok_or_else(|| Error::new("member not found"))?;
+            .ok_or_else(|| ApiError::not_found("member not found"))?;
 
         // Convert the member to ActiveModel and update the deactivated_at field
         let mut member_am: members::ActiveModel = member.into();
@@ -53,7 +54,7 @@ impl Mutation {
             id: member.id.to_string(),
             user_id: member.user_id.to_string(),
         };
-        producer.send(Some(&event), Some(&key)).await?;
+        super::emit(ctx, &event, &key).await?;
 
         Ok(member.into())
     }
@@ -70,20 +71,26 @@ impl Mutation {
     ) -> Result<Member> {
         // Get AppContext and Producer instances from the Context object
         let AppContext { db, .. } = ctx.data::<AppContext>()?;
-        let producer = ctx.data::<Producer<OrganizationEvents>>()?;
 
         // Find a member by ID
         let member = members::Entity::find_by_id(input.id)
             .one(db.get())
             .await?
-            .ok_or_else(|| Error::new("member not found"))?;
+            .ok_or_else(|| ApiError::not_found("member not found"))?;
+
+        // Reactivating a member must not exceed the member limit of the organization's plan, checked in the transaction of the update
+        let txn = db.get().begin().await?;
+
+        quotas::check(&txn, member.organization_id, Resource::Members).await?;
 
         // Convert the member to ActiveModel and clear the deactivated_at field
         let mut member_am: members::ActiveModel = member.into();
         member_am.deactivated_at = Set(None);
 
         // Update the member and return it
-        let member = member_am.update(db.get()).await?;
+        let member = member_am.update(&txn).await?;
+
+        txn.commit().await?;
 
         // Send an event to a message queue
         let event = OrganizationEvents {
@@ -95,7 +102,7 @@ impl Mutation {
             id: member.id.to_string(),
             user_id: member.user_id.to_string(),
         };
-        producer.send(Some(&event), Some(&key)).await?;
+        super::emit(ctx, &event, &key).await?;
 
         Ok(member.into())
     }
@@ -1,8 +1,25 @@
+pub mod api_credential;
+pub mod credit;
 pub mod invite;
 pub mod member;
 pub mod organization;
 pub mod project;
 
+use async_graphql::Context;
+use hub_core::producer::Producer;
+use opentelemetry::{
+    trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer},
+    Context as OtelContext, KeyValue,
+};
+
+use crate::{
+    errors::{ApiError, Result},
+    health::HealthChecker,
+    metrics::Metrics,
+    proto::{OrganizationEventKey, OrganizationEvents},
+    telemetry::{self, TracedEventKey},
+};
+
 // Add your other ones here to create a unified Mutation object
 // e.x. Mutation(OrganizationMutation, OtherMutation, OtherOtherMutation)
 #[derive(Debug, async_graphql::MergedObject, Default)]
@@ -11,4 +28,46 @@ pub struct Mutation(
     project::Mutation,
     invite::Mutation,
     member::Mutation,
+    api_credential::Mutation,
+    credit::Mutation,
 );
+
+/// Sends an organization event in a producer span of the current trace, with the `traceparent` of the span in its key, counting whether Kafka accepted it and reporting failures to the readiness check.
+///
+/// # Errors
+/// This function fails if the event cannot be sent.
+async fn emit(
+    ctx: &Context<'_>,
+    event: &OrganizationEvents,
+    key: &OrganizationEventKey,
+) -> Result<()> {
+    let producer = ctx.data::<Producer<OrganizationEvents>>()?;
+    let metrics = ctx.data::<Metrics>()?;
+    let health_checker = ctx.data::<HealthChecker>()?;
+
+    let tracer = telemetry::tracer();
+    let span = tracer
+        .span_builder("hub-orgs send")
+        .with_kind(SpanKind::Producer)
+        .with_attributes(vec![
+            KeyValue::new("messaging.system", "kafka"),
+            KeyValue::new("messaging.destination", "hub-orgs"),
+            KeyValue::new("messaging.kafka.message_key", key.id.clone()),
+        ])
+        .start(&tracer);
+    let cx = OtelContext::current_with_span(span);
+    let key = TracedEventKey::new(key.clone(), &cx);
+
+    let result = producer
+        .send(Some(event), Some(&key))
+        .with_context(cx.clone())
+        .await;
+    metrics.observe_produce(result.is_ok());
+    health_checker.observe_produce(&result);
+
+    if let Err(e) = &result {
+        cx.span().set_status(Status::error(e.to_string()));
+    }
+
+    result.map_err(ApiError::internal)
+}
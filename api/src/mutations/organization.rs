@@ -1,11 +1,16 @@
-use async_graphql::{self, Context, Error, InputObject, Object, Result, SimpleObject};
-use hub_core::producer::Producer;
+use async_graphql::{self, Context, InputObject, MaybeUndefined, Object, SimpleObject, Upload};
+use hub_core::assets::AssetProxy;
 use sea_orm::{prelude::*, Set};
 
 use crate::{
+    affiliations,
+    cookies::OrganizationCookie,
     entities::{organizations, organizations::ActiveModel, owners},
+    errors::{ApiError, Result},
+    images::{self, ImageUploader},
+    metadata,
     proto::{organization_events::Event, Organization, OrganizationEventKey, OrganizationEvents},
-    AppContext,
+    quotas, rate_limit, AppContext,
 };
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -14,20 +19,39 @@ pub struct Mutation;
 #[Object(name = "OrganizationMutation")]
 impl Mutation {
     /// This mutation creates a new Holaplex organization, with the user triggering the mutation automatically assigned as the owner of the organization.
+    ///
     /// # Errors
-    /// This mutation produces an error if it is unable to connect to the database, emit the organization creation event, or if the user is not set in the X-USER-ID header.
+    /// This mutation produces an error if it is unable to connect to the database, emit the organization creation event, if the user is not set in the X-USER-ID header, or if the rate limit of organization creation has been exceeded.
     pub async fn create_organization(
         &self,
         ctx: &Context<'_>,
-        input: CreateOrganizationInput,
+        mut input: CreateOrganizationInput,
     ) -> Result<CreateOrganizationPayload> {
         let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
         let conn = db.get();
-        let producer = ctx.data::<Producer<OrganizationEvents>>()?;
+        let asset_proxy = ctx.data::<AssetProxy>()?;
 
-        let user_id = user_id.ok_or_else(|| Error::new("X-USER-ID header not found"))?;
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
 
-        let org_model = ActiveModel::from(input.clone()).insert(conn).await?;
+        rate_limit::check(ctx, "create_organization", user_id, None).await?;
+
+        input.profile_image_url =
+            images::validate_optional_url(asset_proxy, input.profile_image_url)?;
+        input.description =
+            metadata::validate_optional(input.description, metadata::validate_description)?;
+        input.website_url =
+            metadata::validate_optional(input.website_url, metadata::validate_website_url)?;
+        input.twitter_handle =
+            metadata::validate_optional(input.twitter_handle, metadata::validate_handle)?;
+        input.instagram_handle =
+            metadata::validate_optional(input.instagram_handle, metadata::validate_handle)?;
+
+        let plan = quotas::default_plan(conn).await?;
+
+        let mut active_model = ActiveModel::from(input);
+        active_model.plan_id = Set(plan.id);
+
+        let org_model = active_model.insert(conn).await?;
 
         let owner = owners::ActiveModel {
             user_id: Set(user_id),
@@ -46,14 +70,17 @@ impl Mutation {
             user_id: user_id.to_string(),
         };
 
-        producer.send(Some(&event), Some(&key)).await?;
+        super::emit(ctx, &event, &key).await?;
 
         Ok(CreateOrganizationPayload {
             organization: org_model.into(),
         })
     }
 
-    /// This mutation edits the name or profile image of the organization.
+    /// This mutation edits the name, profile image or public metadata of the organization.
+    ///
+    /// # Errors
+    /// This mutation produces an error if the organization does not exist or if the profile image URL is not an https or ipfs URL supported by the asset proxy, or if the description, website URL or social handles are invalid.
     pub async fn edit_organization(
         &self,
         ctx: &Context<'_>,
@@ -61,16 +88,44 @@ impl Mutation {
     ) -> Result<EditOrganizationPayload> {
         let AppContext { db, .. } = ctx.data::<AppContext>()?;
         let conn = db.get();
+        let asset_proxy = ctx.data::<AssetProxy>()?;
+
+        let profile_image_url =
+            images::validate_optional_url(asset_proxy, input.profile_image_url)?;
 
         let org = organizations::Entity::find_by_id(input.id)
             .one(conn)
             .await?
-            .ok_or_else(|| Error::new("organization not found"))?;
+            .ok_or_else(|| ApiError::not_found("organization not found"))?;
 
         let mut active_org: organizations::ActiveModel = org.into();
 
         active_org.name = Set(input.name);
-        active_org.profile_image_url = Set(input.profile_image_url);
+        active_org.profile_image_url = Set(profile_image_url);
+
+        if let Some(description) =
+            metadata::validate_edit(input.description, metadata::validate_description)?
+        {
+            active_org.description = Set(description);
+        }
+
+        if let Some(website_url) =
+            metadata::validate_edit(input.website_url, metadata::validate_website_url)?
+        {
+            active_org.website_url = Set(website_url);
+        }
+
+        if let Some(twitter_handle) =
+            metadata::validate_edit(input.twitter_handle, metadata::validate_handle)?
+        {
+            active_org.twitter_handle = Set(twitter_handle);
+        }
+
+        if let Some(instagram_handle) =
+            metadata::validate_edit(input.instagram_handle, metadata::validate_handle)?
+        {
+            active_org.instagram_handle = Set(instagram_handle);
+        }
 
         let org = active_org.update(conn).await?;
 
@@ -78,12 +133,112 @@ impl Mutation {
             organization: org.into(),
         })
     }
+
+    /// This mutation uploads an image file and sets it as the profile image of the organization.
+    ///
+    /// # Errors
+    /// This mutation produces an error if the user is not set in the X-USER-ID header or is not an active owner or member of the organization, the organization does not exist, the rate limit of image uploads has been exceeded, the file is not a PNG, JPEG, GIF or WebP image, the file exceeds the maximum upload size, or the file cannot be stored.
+    pub async fn upload_organization_image(
+        &self,
+        ctx: &Context<'_>,
+        input: UploadOrganizationImageInput,
+    ) -> Result<UploadOrganizationImagePayload> {
+        let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
+        let conn = db.get();
+        let image_uploader = ctx.data::<ImageUploader>()?;
+
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        let affiliations = affiliations::find_active(conn, user_id).await?;
+
+        if !affiliations.contains(input.id) {
+            return Err(ApiError::forbidden(
+                "user not affiliated to the organization",
+            ));
+        }
+
+        rate_limit::check(ctx, "upload_organization_image", user_id, Some(input.id)).await?;
+
+        let org = organizations::Entity::find_by_id(input.id)
+            .one(conn)
+            .await?
+            .ok_or_else(|| ApiError::not_found("organization not found"))?;
+
+        let profile_image_url = image_uploader
+            .upload(
+                input
+                    .file
+                    .value(ctx)
+                    .map_err(|e| ApiError::validation(format!("invalid upload: {e}")))?,
+                &format!("organizations/{}", org.id),
+            )
+            .await?;
+
+        let mut active_org: organizations::ActiveModel = org.into();
+
+        active_org.profile_image_url = Set(Some(profile_image_url));
+
+        let org = active_org.update(conn).await?;
+
+        Ok(UploadOrganizationImagePayload {
+            organization: org.into(),
+        })
+    }
+
+    /// This mutation selects the organization the browser session of the user works in by setting the `_hub_org` cookie, like the `/browser/organizations/:organization/select` endpoint. The selection is restored the next time the user logs in.
+    ///
+    /// # Errors
+    /// This mutation produces an error if it is unable to connect to the database, if the user is not set in the X-USER-ID header, or if the user is not an active owner or member of the organization.
+    pub async fn select_organization(
+        &self,
+        ctx: &Context<'_>,
+        input: SelectOrganizationInput,
+    ) -> Result<SelectOrganizationPayload> {
+        let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
+        let conn = db.get();
+        let organization_cookie = ctx.data::<OrganizationCookie>()?;
+
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        let affiliations = affiliations::find_active(conn, user_id).await?;
+
+        if !affiliations.contains(input.organization) {
+            return Err(ApiError::forbidden(
+                "user not affiliated to the organization",
+            ));
+        }
+
+        let org = organizations::Entity::find_by_id(input.organization)
+            .one(conn)
+            .await?
+            .ok_or_else(|| ApiError::not_found("organization not found"))?;
+
+        affiliations::remember_selection(conn, user_id, org.id).await?;
+
+        let cookie = organization_cookie
+            .header_value(org.id)
+            .ok_or_else(|| ApiError::internal("failed to build organization cookie"))?;
+
+        ctx.append_http_header("Set-Cookie", cookie);
+
+        Ok(SelectOrganizationPayload {
+            organization: org.into(),
+        })
+    }
 }
 
 #[derive(Debug, InputObject, Clone)]
 pub struct CreateOrganizationInput {
     pub name: String,
     pub profile_image_url: Option<String>,
+    /// A short description of the organization, at most 1000 characters.
+    pub description: Option<String>,
+    /// The URL of the organization's website.
+    pub website_url: Option<String>,
+    /// The Twitter handle of the organization.
+    pub twitter_handle: Option<String>,
+    /// The Instagram handle of the organization.
+    pub instagram_handle: Option<String>,
 }
 
 #[derive(Debug, SimpleObject, Clone)]
@@ -96,6 +251,10 @@ impl From<CreateOrganizationInput> for ActiveModel {
         Self {
             name: Set(val.name),
             profile_image_url: Set(val.profile_image_url),
+            description: Set(val.description),
+            website_url: Set(val.website_url),
+            twitter_handle: Set(val.twitter_handle),
+            instagram_handle: Set(val.instagram_handle),
             ..Default::default()
         }
     }
@@ -125,9 +284,47 @@ pub struct EditOrganizationInput {
     pub id: Uuid,
     pub name: String,
     pub profile_image_url: Option<String>,
+    /// A short description of the organization, at most 1000 characters. Omit to keep the current value or pass `null` to clear it.
+    pub description: MaybeUndefined<String>,
+    /// The URL of the organization's website. Omit to keep the current value or pass `null` to clear it.
+    pub website_url: MaybeUndefined<String>,
+    /// The Twitter handle of the organization. Omit to keep the current value or pass `null` to clear it.
+    pub twitter_handle: MaybeUndefined<String>,
+    /// The Instagram handle of the organization. Omit to keep the current value or pass `null` to clear it.
+    pub instagram_handle: MaybeUndefined<String>,
 }
 
 #[derive(Debug, SimpleObject, Clone)]
 pub struct EditOrganizationPayload {
     pub organization: organizations::Organization,
 }
+
+/// The input used for uploading the profile image of an organization.
+#[derive(InputObject)]
+pub struct UploadOrganizationImageInput {
+    /// The ID of the organization.
+    pub id: Uuid,
+    /// The image file, sent as part of a GraphQL multipart request.
+    pub file: Upload,
+}
+
+/// The payload returned by the `uploadOrganizationImage` mutation.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct UploadOrganizationImagePayload {
+    /// The organization with its updated profile image.
+    pub organization: organizations::Organization,
+}
+
+/// The input used for selecting the organization of the browser session.
+#[derive(Debug, InputObject)]
+pub struct SelectOrganizationInput {
+    /// The ID of the organization to select.
+    pub organization: Uuid,
+}
+
+/// The payload returned by the `selectOrganization` mutation.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct SelectOrganizationPayload {
+    /// The organization that was selected.
+    pub organization: organizations::Organization,
+}
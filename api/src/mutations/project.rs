@@ -1,19 +1,32 @@
-use async_graphql::{Context, Error, InputObject, Object, Result, SimpleObject};
-use hub_core::producer::Producer;
-use sea_orm::{prelude::*, Set};
+use std::collections::HashSet;
+
+use async_graphql::{Context, InputObject, MaybeUndefined, Object, SimpleObject, Upload};
+use hub_core::assets::AssetProxy;
+use sea_orm::{prelude::*, DatabaseTransaction, Set, TransactionTrait};
 
 use crate::{
+    affiliations,
     entities::{
         projects,
         projects::{ActiveModel, Project},
     },
+    errors::{self, ApiError, Result},
+    images::{self, ImageUploader},
+    metadata,
     proto::{
         organization_events::Event, OrganizationEventKey, OrganizationEvents,
         Project as ProtoProject,
     },
-    AppContext,
+    quotas::{self, Resource},
+    rate_limit, slug, AppContext,
 };
 
+/// The unique index of the slugs of the projects of an organization.
+const SLUG_INDEX: &str = "projects_organization_id_slug_idx";
+
+/// The number of slugs tried for a new project before giving up when they keep being taken concurrently.
+const SLUG_ATTEMPTS: usize = 5;
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Mutation;
 
@@ -22,18 +35,37 @@ impl Mutation {
     /// This mutation creates a new project under the specified organization.
     ///
     /// # Errors
-    /// This mutation produces an error if it is unable to connect to the database, emit the project creation event, or if the user is not set in the X-USER-ID header.
+    /// This mutation produces an error if it is unable to connect to the database, emit the project creation event, if the user is not set in the X-USER-ID header, if the organization has reached the project limit of its plan, or if the rate limit of project creation has been exceeded.
     pub async fn create_project(
         &self,
         ctx: &Context<'_>,
-        input: CreateProjectInput,
+        mut input: CreateProjectInput,
     ) -> Result<CreateProjectPayload> {
         let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
-        let producer = ctx.data::<Producer<OrganizationEvents>>()?;
+        let asset_proxy = ctx.data::<AssetProxy>()?;
+
+        let id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        rate_limit::check(ctx, "create_project", id, Some(input.organization)).await?;
 
-        let id = user_id.ok_or_else(|| "X-USER-ID header not found")?;
+        input.profile_image_url =
+            images::validate_optional_url(asset_proxy, input.profile_image_url)?;
+        input.description =
+            metadata::validate_optional(input.description, metadata::validate_description)?;
+        input.website_url =
+            metadata::validate_optional(input.website_url, metadata::validate_website_url)?;
+        input.twitter_handle =
+            metadata::validate_optional(input.twitter_handle, metadata::validate_handle)?;
+        input.instagram_handle =
+            metadata::validate_optional(input.instagram_handle, metadata::validate_handle)?;
 
-        let project: Project = ActiveModel::from(input).insert(db.get()).await?.into();
+        let txn = db.get().begin().await?;
+
+        quotas::check(&txn, input.organization, Resource::Projects).await?;
+
+        let project: Project = insert_with_available_slug(&txn, input).await?.into();
+
+        txn.commit().await?;
 
         let event = OrganizationEvents {
             event: Some(Event::ProjectCreated(project.clone().into())),
@@ -44,12 +76,15 @@ impl Mutation {
             user_id: id.to_string(),
         };
 
-        producer.send(Some(&event), Some(&key)).await?;
+        super::emit(ctx, &event, &key).await?;
 
         Ok(CreateProjectPayload { project })
     }
 
-    /// This mutations edits the name and profile image of the project.
+    /// This mutations edits the name, slug, profile image and public metadata of the project.
+    ///
+    /// # Errors
+    /// This mutation produces an error if the project does not exist, the slug is invalid or taken, the profile image URL is not an https or ipfs URL supported by the asset proxy, or if the description, website URL or social handles are invalid.
     pub async fn edit_project(
         &self,
         ctx: &Context<'_>,
@@ -57,21 +92,124 @@ impl Mutation {
     ) -> Result<EditProjectPayload> {
         let AppContext { db, .. } = ctx.data::<AppContext>()?;
         let conn = db.get();
+        let asset_proxy = ctx.data::<AssetProxy>()?;
+
+        let profile_image_url =
+            images::validate_optional_url(asset_proxy, input.profile_image_url)?;
 
         let project = projects::Entity::find_by_id(input.id)
             .one(conn)
             .await?
-            .ok_or_else(|| Error::new("project not found"))?;
+            .ok_or_else(|| ApiError::not_found("project not found"))?;
 
         let mut active_project: projects::ActiveModel = project.into();
 
         active_project.name = Set(input.name);
-        active_project.profile_image_url = Set(input.profile_image_url);
+        active_project.profile_image_url = Set(profile_image_url);
 
-        let project: Project = active_project.update(conn).await?.into();
+        if let Some(description) =
+            metadata::validate_edit(input.description, metadata::validate_description)?
+        {
+            active_project.description = Set(description);
+        }
+
+        if let Some(website_url) =
+            metadata::validate_edit(input.website_url, metadata::validate_website_url)?
+        {
+            active_project.website_url = Set(website_url);
+        }
+
+        if let Some(twitter_handle) =
+            metadata::validate_edit(input.twitter_handle, metadata::validate_handle)?
+        {
+            active_project.twitter_handle = Set(twitter_handle);
+        }
+
+        if let Some(instagram_handle) =
+            metadata::validate_edit(input.instagram_handle, metadata::validate_handle)?
+        {
+            active_project.instagram_handle = Set(instagram_handle);
+        }
+
+        if let Some(slug) = input.slug {
+            if !slug::is_valid(&slug) {
+                return Err(ApiError::validation(
+                    "slug must be lowercase letters and digits separated by single hyphens",
+                ));
+            }
+
+            active_project.slug = Set(slug);
+        }
+
+        let project: Project = active_project
+            .update(conn)
+            .await
+            .map_err(|e| {
+                if errors::is_unique_violation(&e, SLUG_INDEX) {
+                    ApiError::conflict("project slug already in use")
+                } else {
+                    e.into()
+                }
+            })?
+            .into();
 
         Ok(EditProjectPayload { project })
     }
+
+    /// This mutation uploads an image file and sets it as the profile image of the project.
+    ///
+    /// # Errors
+    /// This mutation produces an error if the user is not set in the X-USER-ID header or is not an active owner or member of the organization of the project, the project does not exist, the rate limit of image uploads has been exceeded, the file is not a PNG, JPEG, GIF or WebP image, the file exceeds the maximum upload size, or the file cannot be stored.
+    pub async fn upload_project_image(
+        &self,
+        ctx: &Context<'_>,
+        input: UploadProjectImageInput,
+    ) -> Result<UploadProjectImagePayload> {
+        let AppContext { db, user_id, .. } = ctx.data::<AppContext>()?;
+        let conn = db.get();
+        let image_uploader = ctx.data::<ImageUploader>()?;
+
+        let user_id = user_id.ok_or_else(ApiError::missing_user)?;
+
+        let project = projects::Entity::find_by_id(input.id)
+            .one(conn)
+            .await?
+            .ok_or_else(|| ApiError::not_found("project not found"))?;
+
+        let affiliations = affiliations::find_active(conn, user_id).await?;
+
+        if !affiliations.contains(project.organization_id) {
+            return Err(ApiError::forbidden(
+                "user not affiliated to the organization",
+            ));
+        }
+
+        rate_limit::check(
+            ctx,
+            "upload_project_image",
+            user_id,
+            Some(project.organization_id),
+        )
+        .await?;
+
+        let profile_image_url = image_uploader
+            .upload(
+                input
+                    .file
+                    .value(ctx)
+                    .map_err(|e| ApiError::validation(format!("invalid upload: {e}")))?,
+                &format!("projects/{}", project.id),
+            )
+            .await?;
+
+        let mut active_project: projects::ActiveModel = project.into();
+
+        active_project.profile_image_url = Set(Some(profile_image_url));
+
+        let project: Project = active_project.update(conn).await?.into();
+
+        Ok(UploadProjectImagePayload { project })
+    }
 }
 
 /// The input used for creating a project.
@@ -81,8 +219,16 @@ pub struct CreateProjectInput {
     pub organization: Uuid,
     /// The friendly name to denote the project from others belonging to the organization.
     pub name: String,
-    /// The URL of the project's profile image.
+    /// The URL of the project's profile image. Only `https` and `ipfs` URLs are accepted.
     pub profile_image_url: Option<String>,
+    /// A short description of the project, at most 1000 characters.
+    pub description: Option<String>,
+    /// The URL of the project's website.
+    pub website_url: Option<String>,
+    /// The Twitter handle of the project.
+    pub twitter_handle: Option<String>,
+    /// The Instagram handle of the project.
+    pub instagram_handle: Option<String>,
 }
 
 /**
@@ -102,6 +248,10 @@ impl From<CreateProjectInput> for ActiveModel {
             organization_id: Set(val.organization),
             name: Set(val.name),
             profile_image_url: Set(val.profile_image_url),
+            description: Set(val.description),
+            website_url: Set(val.website_url),
+            twitter_handle: Set(val.twitter_handle),
+            instagram_handle: Set(val.instagram_handle),
             ..Default::default()
         }
     }
@@ -133,9 +283,79 @@ pub struct EditProjectInput {
     pub id: Uuid,
     pub name: String,
     pub profile_image_url: Option<String>,
+    /// The new slug of the project. The current slug is kept when omitted.
+    pub slug: Option<String>,
+    /// A short description of the project, at most 1000 characters. Omit to keep the current value or pass `null` to clear it.
+    pub description: MaybeUndefined<String>,
+    /// The URL of the project's website. Omit to keep the current value or pass `null` to clear it.
+    pub website_url: MaybeUndefined<String>,
+    /// The Twitter handle of the project. Omit to keep the current value or pass `null` to clear it.
+    pub twitter_handle: MaybeUndefined<String>,
+    /// The Instagram handle of the project. Omit to keep the current value or pass `null` to clear it.
+    pub instagram_handle: MaybeUndefined<String>,
 }
 
 #[derive(Debug, SimpleObject)]
 pub struct EditProjectPayload {
     pub project: Project,
 }
+
+/// The input used for uploading the profile image of a project.
+#[derive(InputObject)]
+pub struct UploadProjectImageInput {
+    /// The ID of the project.
+    pub id: Uuid,
+    /// The image file, sent as part of a GraphQL multipart request.
+    pub file: Upload,
+}
+
+/// The payload returned by the `uploadProjectImage` mutation.
+#[derive(Debug, SimpleObject)]
+pub struct UploadProjectImagePayload {
+    /// The project with its updated profile image.
+    pub project: Project,
+}
+
+/// Inserts a new project with a slug picked from its name that is not yet used by another project of the organization. When a project renamed concurrently takes the slug first, the insert is retried with the next free slug.
+async fn insert_with_available_slug(
+    txn: &DatabaseTransaction,
+    input: CreateProjectInput,
+) -> Result<projects::Model> {
+    let name = input.name.clone();
+    let mut taken: HashSet<String> = projects::Entity::find()
+        .filter(projects::Column::OrganizationId.eq(input.organization))
+        .all(txn)
+        .await?
+        .into_iter()
+        .map(|p| p.slug)
+        .collect();
+
+    let active_model = ActiveModel::from(input);
+
+    for _ in 0..SLUG_ATTEMPTS {
+        let slug = slug::unique(&name, &taken);
+        let mut active_model = active_model.clone();
+        active_model.slug = Set(slug.clone());
+
+        // a savepoint, so a taken slug does not abort the transaction holding the quota check
+        let savepoint = txn.begin().await?;
+
+        match active_model.insert(&savepoint).await {
+            Ok(project) => {
+                savepoint.commit().await?;
+
+                return Ok(project);
+            },
+            Err(e) if errors::is_unique_violation(&e, SLUG_INDEX) => {
+                savepoint.rollback().await?;
+
+                taken.insert(slug);
+            },
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err(ApiError::conflict(
+        "no project slug available, retry the request",
+    ))
+}
@@ -0,0 +1,204 @@
+//! Trusted documents: only GraphQL documents from a manifest prepared at build time may be executed.
+//!
+//! The manifest is a JSON object mapping the hex encoded SHA-256 hash of each document to the document. Clients send either the full document or only its hash in the `persistedQuery` extension, as with automatic persisted queries.
+//!
+//! Queries selecting only the `_service` and `_entities` fields are always allowed, as the federation router sends them to compose the supergraph and to resolve entities of other subgraphs.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest},
+    from_value,
+    parser::{
+        parse_query,
+        types::{OperationType, Selection},
+    },
+    ErrorExtensionValues, Request, ServerError, ServerResult,
+};
+use hub_core::anyhow::{anyhow, Context as _, Result};
+use poem::async_trait;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// The root fields of the queries the federation router sends to subgraphs.
+const FEDERATION_FIELDS: [&str; 2] = ["_service", "_entities"];
+
+#[derive(Deserialize)]
+struct PersistedQuery {
+    #[serde(rename = "sha256Hash")]
+    sha256_hash: String,
+}
+
+/// Extension rejecting every document that is not listed in the trusted documents manifest.
+#[derive(Debug, Clone)]
+pub struct TrustedDocuments {
+    documents: Arc<HashMap<String, String>>,
+}
+
+impl TrustedDocuments {
+    /// Loads the trusted documents manifest. Hashes that do not match their document are rejected so a stale manifest is caught at startup.
+    ///
+    /// # Errors
+    /// This function fails if the manifest cannot be read or parsed, or if a hash does not match its document.
+    pub fn load(path: &Path) -> Result<Self> {
+        let manifest = fs::read_to_string(path)
+            .with_context(|| format!("failed to read trusted documents from {}", path.display()))?;
+        let documents: HashMap<String, String> =
+            serde_json::from_str(&manifest).context("failed to parse trusted documents")?;
+
+        if let Some((hash, _)) = documents
+            .iter()
+            .find(|(hash, query)| hash_query(query) != **hash)
+        {
+            return Err(anyhow!(
+                "trusted document {hash} does not match its SHA-256 hash"
+            ));
+        }
+
+        Ok(Self {
+            documents: Arc::new(documents),
+        })
+    }
+}
+
+impl TrustedDocuments {
+    /// The names of the operations of the trusted documents.
+    #[must_use]
+    pub fn operation_names(&self) -> HashSet<String> {
+        self.documents
+            .values()
+            .filter_map(|query| parse_query(query).ok())
+            .flat_map(|document| {
+                document
+                    .operations
+                    .iter()
+                    .filter_map(|(name, _)| name.map(ToString::to_string))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+impl ExtensionFactory for TrustedDocuments {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(self.clone())
+    }
+}
+
+#[async_trait]
+impl Extension for TrustedDocuments {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let persisted_query = request
+            .extensions
+            .remove("persistedQuery")
+            .and_then(|value| from_value::<PersistedQuery>(value).ok());
+
+        let hash = match persisted_query {
+            Some(PersistedQuery { sha256_hash }) => sha256_hash,
+            None if is_federation_query(&request.query) => return next.run(ctx, request).await,
+            None => hash_query(&request.query),
+        };
+
+        let Some(query) = self.documents.get(&hash) else {
+            let mut error = ServerError::new("document is not trusted", None);
+            let mut extensions = ErrorExtensionValues::default();
+            extensions.set("code", "UNTRUSTED_DOCUMENT");
+            error.extensions = Some(extensions);
+
+            return Err(error);
+        };
+
+        request.query = query.clone();
+
+        next.run(ctx, request).await
+    }
+}
+
+fn hash_query(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+/// Whether every operation of the document is a query selecting only federation fields.
+fn is_federation_query(query: &str) -> bool {
+    let Ok(document) = parse_query(query) else {
+        return false;
+    };
+
+    document.operations.iter().all(|(_, operation)| {
+        let operation = &operation.node;
+
+        operation.ty == OperationType::Query
+            && operation
+                .selection_set
+                .node
+                .items
+                .iter()
+                .all(|selection| match &selection.node {
+                    Selection::Field(field) => {
+                        FEDERATION_FIELDS.contains(&field.node.name.node.as_str())
+                    },
+                    Selection::FragmentSpread(_) | Selection::InlineFragment(_) => false,
+                })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_operation_names() {
+        let documents = [
+            "query Organization { organization(id: \"1\") { id } }",
+            "{ __typename }",
+        ]
+        .into_iter()
+        .map(|query| (hash_query(query), query.to_string()))
+        .collect();
+        let trusted_documents = TrustedDocuments {
+            documents: Arc::new(documents),
+        };
+
+        assert_eq!(
+            trusted_documents.operation_names(),
+            HashSet::from(["Organization".to_string()])
+        );
+    }
+
+    #[test]
+    fn allows_federation_queries() {
+        assert!(is_federation_query(
+            "query __ApolloGetServiceDefinition__ { _service { sdl } }"
+        ));
+        assert!(is_federation_query(
+            "query($representations: [_Any!]!) {
+                _entities(representations: $representations) { ... on Organization { id name } }
+            }"
+        ));
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(!is_federation_query(
+            "{ _service { sdl } organization(id: \"1\") { id } }"
+        ));
+        assert!(!is_federation_query(
+            "query A { _service { sdl } } query B { organization(id: \"1\") { id } }"
+        ));
+        assert!(!is_federation_query("mutation { _service { sdl } }"));
+        assert!(!is_federation_query(
+            "{ ...Service } fragment Service on Query { _service { sdl } }"
+        ));
+        assert!(!is_federation_query("{ _service { sdl }"));
+    }
+}
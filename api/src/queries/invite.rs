@@ -1,9 +1,10 @@
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use hub_core::uuid::Uuid;
 use sea_orm::prelude::*;
 
 use crate::{
     entities::invites::{Column, Entity, Model},
+    errors::Result,
     AppContext,
 };
 
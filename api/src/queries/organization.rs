@@ -1,7 +1,7 @@
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Object};
 use sea_orm::prelude::*;
 
-use crate::{entities::organizations, AppContext};
+use crate::{entities::organizations, errors::Result, AppContext};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Query;
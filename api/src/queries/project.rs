@@ -1,8 +1,9 @@
-use async_graphql::{self, Context, Object, Result};
+use async_graphql::{self, Context, Object};
 use sea_orm::prelude::*;
 
 use crate::{
     entities::projects::{self, Project},
+    errors::Result,
     AppContext,
 };
 
@@ -1,8 +1,10 @@
-use async_graphql::{Context, Object, Result, Union};
-use sea_orm::{prelude::*, QueryOrder};
+use async_graphql::{Context, Object, Union};
+use sea_orm::prelude::*;
 
 use crate::{
+    affiliations,
     entities::{members, owners},
+    errors::Result,
     AppContext,
 };
 
@@ -69,25 +71,17 @@ impl User {
         self.id
     }
 
+    #[graphql(complexity = "10 * child_complexity")]
     async fn affiliations(&self, ctx: &Context<'_>) -> Result<Vec<Affiliation>> {
         let AppContext { db, .. } = ctx.data::<AppContext>()?;
-        let user_id = self.id;
-        let conn = db.get();
 
-        let org_owners = owners::Entity::find_by_user(user_id)
-            .order_by_desc(owners::Column::CreatedAt)
-            .all(conn)
-            .await?;
+        let affiliations::Affiliations { owners, members } =
+            affiliations::find_active(db.get(), self.id).await?;
 
-        let org_members = members::Entity::find_active_by_user(user_id)
-            .order_by_desc(members::Column::CreatedAt)
-            .all(conn)
-            .await?;
-
-        Ok(org_owners
+        Ok(owners
             .into_iter()
             .map(Into::into)
-            .chain(org_members.into_iter().map(Into::into))
+            .chain(members.into_iter().map(Into::into))
             .collect())
     }
 }
@@ -1,7 +1,11 @@
-use async_graphql::{ComplexObject, Context, Object, Result, SimpleObject};
+use async_graphql::{ComplexObject, Context, Object, SimpleObject};
 use hub_core::uuid::Uuid;
 
-use crate::{entities::Project, AppContext};
+use crate::{
+    entities::Project,
+    errors::{ApiError, Result},
+    AppContext,
+};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Query;
@@ -37,7 +41,7 @@ pub struct Webhook {
 #[ComplexObject]
 impl Webhook {
     /// This field specifies the list of projects for which an associated object will trigger a webhook event.
-    #[graphql(requires = "channels")]
+    #[graphql(requires = "channels", complexity = "10 * child_complexity")]
     async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<Project>> {
         let AppContext { project_loader, .. } = ctx.data::<AppContext>()?;
 
@@ -45,7 +49,8 @@ impl Webhook {
             .channels
             .iter()
             .map(|channel| Uuid::parse_str(channel))
-            .collect::<Result<Vec<Uuid>, _>>()?;
+            .collect::<Result<Vec<Uuid>, _>>()
+            .map_err(|e| ApiError::validation(format!("invalid channel: {e}")))?;
 
         let projects = project_loader
             .load_many(project_ids)
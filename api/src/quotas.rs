@@ -0,0 +1,188 @@
+//! Plan limits on the projects, members and pending invitations of an organization.
+
+use async_graphql::SimpleObject;
+use sea_orm::{prelude::*, ConnectionTrait, DatabaseTransaction, PaginatorTrait, QuerySelect};
+
+use crate::{
+    entities::{
+        invites, members, organizations, plans, projects, sea_orm_active_enums::InviteStatus,
+    },
+    errors::{ApiError, Result},
+};
+
+/// The name of the plan assigned to newly created organizations.
+pub const DEFAULT_PLAN: &str = "free";
+
+/// A resource of an organization limited by its plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// Projects that have not been deactivated.
+    Projects,
+    /// Members that have neither been revoked nor deactivated.
+    Members,
+    /// Invitations that have been sent and not yet accepted or revoked.
+    PendingInvites,
+}
+
+impl Resource {
+    fn limit(self, plan: &plans::Model) -> Option<i32> {
+        match self {
+            Self::Projects => plan.max_projects,
+            Self::Members => plan.max_members,
+            Self::PendingInvites => plan.max_pending_invites,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Projects => "projects",
+            Self::Members => "members",
+            Self::PendingInvites => "pending invites",
+        }
+    }
+
+    /// Counts the resources the organization currently uses.
+    ///
+    /// # Errors
+    /// This function fails if the database query fails.
+    pub async fn usage<C: ConnectionTrait>(self, conn: &C, organization: Uuid) -> Result<u64> {
+        let count = match self {
+            Self::Projects => {
+                projects::Entity::find()
+                    .filter(projects::Column::OrganizationId.eq(organization))
+                    .filter(projects::Column::DeactivatedAt.is_null())
+                    .count(conn)
+                    .await?
+            },
+            Self::Members => {
+                members::Entity::find()
+                    .filter(members::Column::OrganizationId.eq(organization))
+                    .filter(members::Column::RevokedAt.is_null())
+                    .filter(members::Column::DeactivatedAt.is_null())
+                    .count(conn)
+                    .await?
+            },
+            Self::PendingInvites => {
+                invites::Entity::find()
+                    .filter(invites::Column::OrganizationId.eq(organization))
+                    .filter(invites::Column::Status.eq(InviteStatus::Sent))
+                    .count(conn)
+                    .await?
+            },
+        };
+
+        Ok(count)
+    }
+}
+
+/// The usage of a resource of an organization compared to the limit of its plan.
+#[derive(Debug, Clone, Copy, SimpleObject)]
+pub struct Quota {
+    /// The amount of the resource the organization currently uses.
+    pub used: i64,
+    /// The maximum amount of the resource allowed by the plan of the organization, `null` when unlimited.
+    pub limit: Option<i32>,
+}
+
+/// The usage of the resources of an organization compared to the limits of its plan.
+#[derive(Debug, Clone, Copy, SimpleObject)]
+pub struct OrganizationQuotas {
+    /// The active projects of the organization.
+    pub projects: Quota,
+    /// The active members of the organization.
+    pub members: Quota,
+    /// The invitations of the organization awaiting acceptance.
+    pub pending_invites: Quota,
+}
+
+/// Fetches the plan new organizations are assigned to.
+///
+/// # Errors
+/// This function fails if the database query fails or the default plan does not exist.
+pub async fn default_plan(conn: &DatabaseConnection) -> Result<plans::Model> {
+    plans::Entity::find_by_name(DEFAULT_PLAN)
+        .one(conn)
+        .await?
+        .ok_or_else(|| ApiError::internal("default plan not found"))
+}
+
+/// Fetches the plan of an organization.
+///
+/// # Errors
+/// This function fails if the database query fails or the organization does not exist.
+pub async fn plan<C: ConnectionTrait>(conn: &C, organization: Uuid) -> Result<plans::Model> {
+    organizations::Entity::find_by_id(organization)
+        .find_also_related(plans::Entity)
+        .one(conn)
+        .await?
+        .and_then(|(_, plan)| plan)
+        .ok_or_else(|| ApiError::not_found("organization not found"))
+}
+
+/// Computes the usage of every limited resource of an organization.
+///
+/// # Errors
+/// This function fails if the database queries fail or the organization does not exist.
+pub async fn usage(conn: &DatabaseConnection, organization: Uuid) -> Result<OrganizationQuotas> {
+    let plan = plan(conn, organization).await?;
+
+    let quota = |resource: Resource, used: u64| Quota {
+        used: i64::try_from(used).unwrap_or(i64::MAX),
+        limit: resource.limit(&plan),
+    };
+
+    Ok(OrganizationQuotas {
+        projects: quota(
+            Resource::Projects,
+            Resource::Projects.usage(conn, organization).await?,
+        ),
+        members: quota(
+            Resource::Members,
+            Resource::Members.usage(conn, organization).await?,
+        ),
+        pending_invites: quota(
+            Resource::PendingInvites,
+            Resource::PendingInvites.usage(conn, organization).await?,
+        ),
+    })
+}
+
+/// Checks that the organization can add one more of `resource` without exceeding the limit of its plan.
+///
+/// The organization row is locked until `txn` ends, so the resource must be added in the same transaction. Concurrent checks for the organization then wait for it instead of counting the same usage.
+///
+/// # Errors
+/// This function fails with a `QUOTA_EXCEEDED` error if the limit is reached, or if the database queries fail or the organization does not exist.
+pub async fn check(
+    txn: &DatabaseTransaction,
+    organization: Uuid,
+    resource: Resource,
+) -> Result<()> {
+    organizations::Entity::find_by_id(organization)
+        .lock_exclusive()
+        .one(txn)
+        .await?
+        .ok_or_else(|| ApiError::not_found("organization not found"))?;
+
+    let plan = plan(txn, organization).await?;
+
+    let Some(limit) = resource.limit(&plan) else {
+        return Ok(());
+    };
+
+    let used = resource.usage(txn, organization).await?;
+
+    if used < u64::try_from(limit).unwrap_or_default() {
+        return Ok(());
+    }
+
+    Err(ApiError::QuotaExceeded {
+        message: format!(
+            "the {} plan allows at most {limit} {}",
+            plan.name,
+            resource.label()
+        ),
+        resource: resource.label(),
+        limit,
+    })
+}
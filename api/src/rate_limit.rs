@@ -0,0 +1,328 @@
+//! Rate limits on the mutations of the GraphQL API, counted per user and per target organization, and on the internal endpoints, counted per client address.
+//!
+//! Quotas are configured per operation as `<operation>=<requests>/<seconds>`, where the operation is the snake case name of the mutation or handler, for example `invite_member=20/3600`. Operations without a quota are not limited.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_graphql::Context;
+use hub_core::{
+    anyhow::{self, anyhow},
+    clap,
+    prelude::*,
+    uuid::Uuid,
+};
+use poem::async_trait;
+
+use crate::errors::{ApiError, Result};
+
+/// The number of tracked windows above which expired windows are pruned from the in-memory backend.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// The number of requests allowed in a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+/// The quota of an operation, parsed from `<operation>=<requests>/<seconds>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationQuota {
+    pub operation: String,
+    pub quota: Quota,
+}
+
+impl FromStr for OperationQuota {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (operation, quota) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected <operation>=<requests>/<seconds>, got {s:?}"))?;
+        let (requests, seconds) = quota
+            .split_once('/')
+            .ok_or_else(|| anyhow!("expected <requests>/<seconds>, got {quota:?}"))?;
+
+        let requests = requests.trim().parse()?;
+        let seconds: u64 = seconds.trim().parse()?;
+
+        if requests == 0 || seconds == 0 {
+            return Err(anyhow!("the quota of {operation} must be positive"));
+        }
+
+        Ok(Self {
+            operation: operation.trim().to_string(),
+            quota: Quota {
+                requests,
+                period: Duration::from_secs(seconds),
+            },
+        })
+    }
+}
+
+/// Arguments for configuring the rate limits of mutations
+#[derive(Debug, clap::Args)]
+pub struct RateLimitArgs {
+    /// The quotas of each user, per operation.
+    #[arg(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "create_organization=5/3600,create_project=30/3600,invite_member=20/3600,create_api_credential=30/3600,upload_organization_image=30/3600,upload_project_image=30/3600"
+    )]
+    pub rate_limit_per_user: Vec<OperationQuota>,
+    /// The quotas of each target organization, shared by all of its users, per operation.
+    #[arg(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "create_project=100/3600,invite_member=100/3600,create_api_credential=100/3600,upload_organization_image=100/3600,upload_project_image=100/3600"
+    )]
+    pub rate_limit_per_organization: Vec<OperationQuota>,
+    /// The quotas of each client address of the internal endpoints, per operation.
+    #[arg(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "verify_credential=6000/60"
+    )]
+    pub rate_limit_per_client: Vec<OperationQuota>,
+}
+
+/// A store of request counts.
+#[async_trait]
+pub trait Backend: Send + Sync + 'static {
+    /// Counts a request against the quota of every key. Returns how long to wait before retrying when any of the quotas is exhausted, in which case the request is counted against none of them.
+    async fn hit(&self, limits: &[(String, Quota)]) -> Option<Duration>;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    resets_at: Instant,
+    requests: u32,
+}
+
+/// Counts requests in fixed windows held in the memory of the process. Counts are not shared between replicas.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    windows: Mutex<HashMap<String, Window>>,
+}
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    async fn hit(&self, limits: &[(String, Quota)]) -> Option<Duration> {
+        let now = Instant::now();
+        let mut windows = self
+            .windows
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, window| window.resets_at > now);
+        }
+
+        let retry_after = limits
+            .iter()
+            .filter_map(|(key, quota)| {
+                let window = windows.get(key)?;
+
+                (now < window.resets_at && window.requests >= quota.requests)
+                    .then(|| window.resets_at - now)
+            })
+            .max();
+
+        if retry_after.is_some() {
+            return retry_after;
+        }
+
+        for (key, quota) in limits {
+            let fresh = Window {
+                resets_at: now + quota.period,
+                requests: 0,
+            };
+            let window = windows.entry(key.clone()).or_insert(fresh);
+
+            if now >= window.resets_at {
+                *window = fresh;
+            }
+
+            window.requests += 1;
+        }
+
+        None
+    }
+}
+
+/// Checks requests against the configured quotas.
+#[derive(Clone)]
+pub struct RateLimiter {
+    backend: Arc<dyn Backend>,
+    per_user: Arc<HashMap<String, Quota>>,
+    per_organization: Arc<HashMap<String, Quota>>,
+    per_client: Arc<HashMap<String, Quota>>,
+}
+
+impl RateLimiter {
+    #[must_use]
+    pub fn new(args: RateLimitArgs, backend: Arc<dyn Backend>) -> Self {
+        let RateLimitArgs {
+            rate_limit_per_user,
+            rate_limit_per_organization,
+            rate_limit_per_client,
+        } = args;
+
+        let quotas = |quotas: Vec<OperationQuota>| {
+            Arc::new(
+                quotas
+                    .into_iter()
+                    .map(|OperationQuota { operation, quota }| (operation, quota))
+                    .collect(),
+            )
+        };
+
+        Self {
+            backend,
+            per_user: quotas(rate_limit_per_user),
+            per_organization: quotas(rate_limit_per_organization),
+            per_client: quotas(rate_limit_per_client),
+        }
+    }
+
+    /// Counts a request to `operation` by `user` targeting `organization`. Returns how long to wait before retrying when a quota is exhausted, in which case the request is counted against neither quota.
+    pub async fn hit(
+        &self,
+        operation: &str,
+        user: Uuid,
+        organization: Option<Uuid>,
+    ) -> Option<Duration> {
+        let mut limits = Vec::with_capacity(2);
+
+        if let Some(quota) = self.per_user.get(operation) {
+            limits.push((format!("{operation}:user:{user}"), *quota));
+        }
+
+        if let (Some(quota), Some(organization)) =
+            (self.per_organization.get(operation), organization)
+        {
+            limits.push((format!("{operation}:organization:{organization}"), *quota));
+        }
+
+        if limits.is_empty() {
+            return None;
+        }
+
+        self.backend.hit(&limits).await
+    }
+
+    /// Counts a request to `operation` by the client at `address`. Returns how long to wait before retrying when the quota of the client is exhausted.
+    pub async fn hit_client(&self, operation: &str, address: IpAddr) -> Option<Duration> {
+        let quota = self.per_client.get(operation)?;
+
+        self.backend
+            .hit(&[(format!("{operation}:client:{address}"), *quota)])
+            .await
+    }
+}
+
+/// The whole seconds to wait before retrying, rounded up so clients never retry before the window resets.
+#[must_use]
+pub fn retry_after_secs(retry_after: Duration) -> u64 {
+    (retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)).max(1)
+}
+
+/// Counts a request to `operation` by `user` targeting `organization` against the rate limits in the context.
+///
+/// # Errors
+/// This function fails with a `RATE_LIMITED` error carrying the seconds to wait in `retryAfter`, and sets the `Retry-After` response header, when a quota is exhausted.
+pub async fn check(
+    ctx: &Context<'_>,
+    operation: &str,
+    user: Uuid,
+    organization: Option<Uuid>,
+) -> Result<()> {
+    let rate_limiter = ctx.data::<RateLimiter>()?;
+
+    let Some(retry_after) = rate_limiter.hit(operation, user, organization).await else {
+        return Ok(());
+    };
+
+    let retry_after = retry_after_secs(retry_after);
+
+    ctx.append_http_header("Retry-After", retry_after.to_string());
+
+    Err(ApiError::RateLimited {
+        message: format!("too many {operation} requests, retry in {retry_after} seconds"),
+        retry_after,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(
+            RateLimitArgs {
+                rate_limit_per_user: vec!["invite_member=2/3600".parse().unwrap()],
+                rate_limit_per_organization: vec!["invite_member=1/3600".parse().unwrap()],
+                rate_limit_per_client: vec!["verify_credential=1/3600".parse().unwrap()],
+            },
+            Arc::new(MemoryBackend::default()),
+        )
+    }
+
+    #[async_std::test]
+    async fn counts_requests_against_neither_quota_when_one_is_exhausted() {
+        let limiter = limiter();
+        let user = Uuid::new_v4();
+        let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        assert_eq!(limiter.hit("invite_member", user, Some(first)).await, None);
+        assert!(limiter
+            .hit("invite_member", user, Some(first))
+            .await
+            .is_some());
+
+        // the rejected request did not use up the second request of the user
+        assert_eq!(limiter.hit("invite_member", user, Some(second)).await, None);
+        assert!(limiter
+            .hit("invite_member", user, Some(third))
+            .await
+            .is_some());
+    }
+
+    #[async_std::test]
+    async fn counts_client_requests_per_address() {
+        let limiter = limiter();
+        let (first, second) = ([10, 0, 0, 1].into(), [10, 0, 0, 2].into());
+
+        assert_eq!(limiter.hit_client("verify_credential", first).await, None);
+        assert!(limiter
+            .hit_client("verify_credential", first)
+            .await
+            .is_some());
+        assert_eq!(limiter.hit_client("verify_credential", second).await, None);
+    }
+
+    #[test]
+    fn rounds_retry_after_up_to_whole_seconds() {
+        assert_eq!(retry_after_secs(Duration::from_millis(1)), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_secs(Duration::from_secs(3)), 3);
+    }
+
+    #[async_std::test]
+    async fn ignores_operations_without_quotas() {
+        let limiter = limiter();
+        let user = Uuid::new_v4();
+
+        for _ in 0..10 {
+            assert_eq!(limiter.hit("create_webhook", user, None).await, None);
+        }
+    }
+}
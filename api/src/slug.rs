@@ -0,0 +1,65 @@
+//! URL-safe identifiers derived from user supplied names.
+
+use std::collections::HashSet;
+
+/// The maximum length of a slug, matching the `character varying(63)` column type.
+pub const MAX_LENGTH: usize = 63;
+
+const FALLBACK: &str = "project";
+
+/// Converts a name into a lowercase slug made of ASCII letters, digits and single hyphens.
+/// Returns an empty string when the name has no usable characters.
+#[must_use]
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    truncate(&slug, MAX_LENGTH).to_string()
+}
+
+/// Checks that a slug is non-empty, at most [`MAX_LENGTH`] characters long and made of
+/// lowercase ASCII letters and digits separated by single hyphens.
+#[must_use]
+pub fn is_valid(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_LENGTH
+        && slug.split('-').all(|part| {
+            !part.is_empty() && part.bytes().all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9'))
+        })
+}
+
+/// Derives a slug from `name` that is not contained in `taken` by appending an incrementing
+/// numeric suffix when the plain slug is already in use.
+#[must_use]
+pub fn unique(name: &str, taken: &HashSet<String>) -> String {
+    let base = slugify(name);
+    let base = if base.is_empty() { FALLBACK } else { &base };
+
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+
+    let mut n = 2;
+
+    loop {
+        let suffix = format!("-{n}");
+        let candidate = format!("{}{suffix}", truncate(base, MAX_LENGTH - suffix.len()));
+
+        if !taken.contains(&candidate) {
+            return candidate;
+        }
+
+        n += 1;
+    }
+}
+
+fn truncate(slug: &str, len: usize) -> &str {
+    slug[..slug.len().min(len)].trim_end_matches('-')
+}
@@ -0,0 +1,99 @@
+//! Storage backends for files uploaded through the GraphQL API.
+
+use std::path::PathBuf;
+
+use hub_core::{
+    anyhow::{anyhow, Result},
+    clap,
+    prelude::*,
+    tokio,
+    url::Url,
+};
+use poem::async_trait;
+
+/// Arguments for configuring where uploaded files are stored
+#[derive(Debug, clap::Args)]
+pub struct StorageArgs {
+    /// The directory uploaded files are written to by the local storage backend.
+    #[arg(long, env, default_value = "uploads")]
+    pub upload_dir: PathBuf,
+    /// The public `https` base URL under which files of the upload directory are served. `http` is accepted for `localhost` during development.
+    #[arg(long, env)]
+    pub upload_base_url: Url,
+    /// The maximum size, in bytes, of an uploaded file.
+    #[arg(long, env, default_value_t = 5 * 1024 * 1024)]
+    pub upload_max_size: u64,
+}
+
+/// A backend that persists uploaded files and makes them publicly retrievable.
+#[async_trait]
+pub trait Storage: Send + Sync + 'static {
+    /// Stores `content` under `key` and returns the public URL of the stored file.
+    ///
+    /// # Errors
+    /// This function fails if the backend is unable to persist the file.
+    async fn put(&self, key: &str, content_type: &str, content: Vec<u8>) -> Result<Url>;
+}
+
+/// Stores uploaded files on the local filesystem, intended for development and testing.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    dir: PathBuf,
+    base_url: Url,
+}
+
+impl LocalStorage {
+    /// Creates a backend writing to `dir` and serving files under `base_url`.
+    ///
+    /// # Errors
+    /// This function fails if `base_url` does not use the `https` scheme, as the URLs of uploaded files must pass the same validation as profile image URLs submitted by clients. Plain `http` is only accepted for `localhost`, where the development server serves the upload directory itself.
+    pub fn new(dir: PathBuf, base_url: Url) -> Result<Self> {
+        let local = base_url.scheme() == "http" && base_url.host_str() == Some("localhost");
+
+        if base_url.scheme() != "https" && !local {
+            return Err(anyhow!(
+                "the upload base URL must use the https scheme, or http on localhost"
+            ));
+        }
+
+        Ok(Self { dir, base_url })
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, _content_type: &str, content: Vec<u8>) -> Result<Url> {
+        let path = self.dir.join(key);
+
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(&path, content)
+        })
+        .await?
+        .context("failed to write uploaded file")?;
+
+        let url = format!("{}/{key}", self.base_url.as_str().trim_end_matches('/'));
+
+        Url::parse(&url).context("failed to build uploaded file URL")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(base_url: &str) -> Result<LocalStorage> {
+        LocalStorage::new(PathBuf::from("uploads"), base_url.parse()?)
+    }
+
+    #[test]
+    fn only_accepts_http_base_urls_on_localhost() {
+        assert!(storage("https://assets.holaplex.com/uploads").is_ok());
+        assert!(storage("http://localhost:3003/uploads").is_ok());
+        assert!(storage("http://assets.holaplex.com/uploads").is_err());
+        assert!(storage("ftp://localhost/uploads").is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! OpenTelemetry tracing of HTTP requests, GraphQL operations and resolvers, SQL queries and Kafka produce calls.
+//!
+//! Spans are exported over OTLP when an endpoint is configured, and otherwise discarded by the no-op global tracer. The trace context of incoming requests is read from the W3C `traceparent` header, and is passed on to the consumers of the organization events in the `traceparent` field of their keys.
+
+use std::{collections::HashMap, time::SystemTime};
+
+use hub_core::{anyhow::Result, clap, prelude::*};
+use opentelemetry::{
+    global::{self, BoxedTracer},
+    runtime,
+    sdk::{
+        propagation::TraceContextPropagator,
+        trace::{self, Sampler},
+        Resource,
+    },
+    trace::{Span, SpanKind, Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use prost::{
+    bytes::{Buf, BufMut},
+    encoding::{string, DecodeContext, WireType},
+    DecodeError, Message,
+};
+use sea_orm::metric;
+
+use crate::proto::OrganizationEventKey;
+
+/// The name of the tracer spans are recorded with.
+const TRACER_NAME: &str = "hub-orgs";
+
+/// The field number of the `traceparent` in the keys of produced events. It is outside of the fields of the `OrganizationEventKey` schema, so consumers decoding keys with the schema skip it.
+const TRACEPARENT_TAG: u32 = 1000;
+
+/// Arguments for configuring the export of traces
+#[derive(Debug, clap::Args)]
+pub struct TelemetryArgs {
+    /// The gRPC endpoint of the OTLP collector traces are exported to. Traces are not exported when it is not set.
+    #[arg(long, env)]
+    pub otel_exporter_otlp_endpoint: Option<String>,
+    /// The name of the service reported in exported traces.
+    #[arg(long, env, default_value = "hub-orgs")]
+    pub otel_service_name: String,
+    /// The ratio of traces sampled when the incoming request is not already part of a sampled trace.
+    #[arg(long, env, default_value_t = 1.0)]
+    pub otel_traces_sampler_ratio: f64,
+}
+
+/// Installs the W3C trace context propagator and, when an OTLP endpoint is configured, the exporting tracer provider. Must be called from within the Tokio runtime.
+///
+/// # Errors
+/// This function fails if the OTLP exporter cannot be built.
+pub fn init(args: TelemetryArgs) -> Result<()> {
+    let TelemetryArgs {
+        otel_exporter_otlp_endpoint,
+        otel_service_name,
+        otel_traces_sampler_ratio,
+    } = args;
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let Some(endpoint) = otel_exporter_otlp_endpoint else {
+        return Ok(());
+    };
+
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint.clone()),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    otel_traces_sampler_ratio,
+                ))))
+                .with_resource(Resource::new([KeyValue::new(
+                    "service.name",
+                    otel_service_name,
+                )])),
+        )
+        .install_batch(runtime::Tokio)?;
+
+    info!("exporting traces to {endpoint}");
+
+    Ok(())
+}
+
+/// Flushes the spans that have not been exported yet.
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// The tracer of the service.
+#[must_use]
+pub fn tracer() -> BoxedTracer {
+    global::tracer(TRACER_NAME)
+}
+
+/// Records a SQL query executed by `SeaORM` as a span of the current trace. Queries run outside of a traced request, such as those of consumed events, are not recorded.
+pub fn record_query(info: &metric::Info<'_>) {
+    let cx = Context::current();
+
+    if !cx.has_active_span() {
+        return;
+    }
+
+    let tracer = tracer();
+    let end = SystemTime::now();
+
+    let mut span = tracer
+        .span_builder("db.query")
+        .with_kind(SpanKind::Client)
+        .with_start_time(end - info.elapsed)
+        .with_attributes(vec![
+            KeyValue::new("db.system", "postgresql"),
+            KeyValue::new("db.statement", info.statement.sql.clone()),
+        ])
+        .start_with_context(&tracer, &cx);
+
+    if info.failed {
+        span.set_status(Status::error("query failed"));
+    }
+
+    span.end_with_timestamp(end);
+}
+
+/// The key of a produced organization event: the fields of its `OrganizationEventKey` followed by the W3C `traceparent` of the trace it was sent in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TracedEventKey {
+    pub key: OrganizationEventKey,
+    pub traceparent: String,
+}
+
+impl TracedEventKey {
+    /// Attaches the trace context `cx` to `key`. The `traceparent` is left empty when `cx` has no span.
+    #[must_use]
+    pub fn new(key: OrganizationEventKey, cx: &Context) -> Self {
+        let mut carrier = HashMap::new();
+        global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut carrier));
+
+        Self {
+            key,
+            traceparent: carrier.remove("traceparent").unwrap_or_default(),
+        }
+    }
+}
+
+impl Message for TracedEventKey {
+    fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+        self.key.encode_raw(buf);
+
+        if !self.traceparent.is_empty() {
+            string::encode(TRACEPARENT_TAG, &self.traceparent, buf);
+        }
+    }
+
+    fn merge_field<B: Buf>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        if tag == TRACEPARENT_TAG {
+            string::merge(wire_type, &mut self.traceparent, buf, ctx)
+        } else {
+            self.key.merge_field(tag, wire_type, buf, ctx)
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        let traceparent = if self.traceparent.is_empty() {
+            0
+        } else {
+            string::encoded_len(TRACEPARENT_TAG, &self.traceparent)
+        };
+
+        self.key.encoded_len() + traceparent
+    }
+
+    fn clear(&mut self) {
+        self.key.clear();
+        self.traceparent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+
+    use super::*;
+
+    #[test]
+    fn keys_carry_the_traceparent_after_the_organization_event_key() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let cx = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+        let key = OrganizationEventKey {
+            id: "organization".to_string(),
+            user_id: "user".to_string(),
+        };
+
+        let traced = TracedEventKey::new(key.clone(), &cx);
+        let bytes = traced.encode_to_vec();
+
+        assert_eq!(
+            traced.traceparent,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(OrganizationEventKey::decode(bytes.as_slice()), Ok(key));
+        assert_eq!(TracedEventKey::decode(bytes.as_slice()), Ok(traced));
+    }
+}
@@ -0,0 +1,140 @@
+//! Profiles of hub users, resolved from the Ory identity admin API.
+
+use std::collections::HashMap;
+
+use async_graphql::SimpleObject;
+use hub_core::{
+    anyhow::{anyhow, Result},
+    clap,
+    futures_util::future::join_all,
+    url::Url,
+    uuid::Uuid,
+};
+use ory_openapi_generated_client::{
+    apis::{configuration::Configuration, identity_api, Error as OryError},
+    models::Identity,
+};
+use poem::async_trait;
+use serde_json::Value;
+
+/// Arguments for configuring the Ory identity admin API user profiles are read from
+#[derive(Debug, clap::Args)]
+pub struct UserArgs {
+    /// The admin URL of the Ory instance holding the identities of hub users. User profiles resolve to `null` when it is not set.
+    #[arg(long, env)]
+    pub ory_admin_url: Option<Url>,
+    /// The API key used to authenticate with the Ory admin API.
+    #[arg(long, env)]
+    pub ory_admin_token: Option<String>,
+}
+
+/// The public profile of a hub user.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct UserProfile {
+    /// The ID of the user.
+    pub id: Uuid,
+    /// The full name of the user.
+    pub name: Option<String>,
+    /// The email address of the user.
+    pub email: Option<String>,
+    /// The URL of the avatar of the user.
+    pub avatar_url: Option<String>,
+}
+
+/// A source of user profiles.
+#[async_trait]
+pub trait UserDirectory: Send + Sync + 'static {
+    /// Fetches the profiles of the users with the given IDs. Users that do not exist are omitted from the result.
+    ///
+    /// # Errors
+    /// This function fails if the directory cannot be queried.
+    async fn profiles(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, UserProfile>>;
+}
+
+/// A directory without any users, used when no identity provider is configured.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmptyDirectory;
+
+#[async_trait]
+impl UserDirectory for EmptyDirectory {
+    async fn profiles(&self, _ids: &[Uuid]) -> Result<HashMap<Uuid, UserProfile>> {
+        Ok(HashMap::new())
+    }
+}
+
+/// Reads user profiles from the traits of Ory identities.
+#[derive(Debug, Clone)]
+pub struct OryDirectory {
+    config: Configuration,
+}
+
+impl OryDirectory {
+    #[must_use]
+    pub fn new(admin_url: &Url, token: Option<String>) -> Self {
+        Self {
+            config: Configuration {
+                base_path: admin_url.as_str().trim_end_matches('/').to_string(),
+                bearer_access_token: token,
+                ..Configuration::default()
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl UserDirectory for OryDirectory {
+    async fn profiles(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, UserProfile>> {
+        let identities = join_all(ids.iter().map(|id| async move {
+            let id_str = id.to_string();
+
+            match identity_api::get_identity(&self.config, &id_str, None).await {
+                Ok(identity) => Ok(Some((*id, profile(*id, &identity)))),
+                Err(OryError::ResponseError(res)) if res.status.as_u16() == 404 => Ok(None),
+                Err(e) => Err(anyhow!("failed to fetch identity {id}: {e}")),
+            }
+        }))
+        .await;
+
+        let mut profiles = HashMap::new();
+
+        for identity in identities {
+            if let Some((id, profile)) = identity? {
+                profiles.insert(id, profile);
+            }
+        }
+
+        Ok(profiles)
+    }
+}
+
+/// Builds the profile of a user from the traits of their Ory identity. The name trait is either a string or an object with `first` and `last` names.
+fn profile(id: Uuid, identity: &Identity) -> UserProfile {
+    let traits = identity.traits.as_ref();
+    let text = |key: &str| {
+        traits
+            .and_then(|traits| traits.get(key))
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+    };
+
+    let name = match traits.and_then(|traits| traits.get("name")) {
+        Some(Value::String(name)) => Some(name.clone()),
+        Some(Value::Object(name)) => {
+            let parts: Vec<&str> = ["first", "last"]
+                .iter()
+                .filter_map(|part| name.get(*part).and_then(Value::as_str))
+                .filter(|part| !part.is_empty())
+                .collect();
+
+            (!parts.is_empty()).then(|| parts.join(" "))
+        },
+        _ => None,
+    };
+
+    UserProfile {
+        id,
+        name,
+        email: text("email"),
+        avatar_url: text("profile_image"),
+    }
+}
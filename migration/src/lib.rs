@@ -24,6 +24,15 @@ mod m20230510_154238_set_default_timestamp_to_utc;
 mod m20230518_034509_set_default_timestamp;
 mod m20230519_050922_change_email_datatype_to_citext;
 mod m20230622_150710_remove_unique_org_name_constraint;
+mod m20230705_101532_add_slug_to_projects;
+mod m20230712_093412_add_metadata_to_organizations;
+mod m20230712_093418_add_metadata_to_projects;
+mod m20230719_141207_create_api_credentials_table;
+mod m20230726_083145_create_project_stats_table;
+mod m20230802_104511_create_plans_table;
+mod m20230809_131722_create_credit_ledger_table;
+mod m20230816_092214_create_organization_selections_table;
+mod m20230823_101507_create_processed_events_table;
 
 pub struct Migrator;
 
@@ -55,6 +64,15 @@ impl MigratorTrait for Migrator {
             Box::new(m20230518_034509_set_default_timestamp::Migration),
             Box::new(m20230519_050922_change_email_datatype_to_citext::Migration),
             Box::new(m20230622_150710_remove_unique_org_name_constraint::Migration),
+            Box::new(m20230705_101532_add_slug_to_projects::Migration),
+            Box::new(m20230712_093412_add_metadata_to_organizations::Migration),
+            Box::new(m20230712_093418_add_metadata_to_projects::Migration),
+            Box::new(m20230719_141207_create_api_credentials_table::Migration),
+            Box::new(m20230726_083145_create_project_stats_table::Migration),
+            Box::new(m20230802_104511_create_plans_table::Migration),
+            Box::new(m20230809_131722_create_credit_ledger_table::Migration),
+            Box::new(m20230816_092214_create_organization_selections_table::Migration),
+            Box::new(m20230823_101507_create_processed_events_table::Migration),
         ]
     }
 }
@@ -0,0 +1,109 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Statement},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Projects::Slug).custom(ColumnType::CharVarying),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Backfill existing projects from their names. Projects that would collide within the
+        // same organization are disambiguated with the first segment of their ID.
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"WITH slugs AS (
+                SELECT id, organization_id, COALESCE(
+                    NULLIF(TRIM(BOTH '-' FROM LEFT(REGEXP_REPLACE(LOWER(name), '[^a-z0-9]+', '-', 'g'), 54)), ''),
+                    'project'
+                ) AS base
+                FROM projects
+            ), ranked AS (
+                SELECT id, base, ROW_NUMBER() OVER (
+                    PARTITION BY organization_id, base ORDER BY created_at, id
+                ) AS position
+                FROM slugs
+            )
+            UPDATE projects
+            SET slug = CASE
+                WHEN ranked.position = 1 THEN ranked.base
+                ELSE ranked.base || '-' || LEFT(projects.id::text, 8)
+            END
+            FROM ranked
+            WHERE projects.id = ranked.id;"#
+                .to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"ALTER TABLE projects
+            ALTER COLUMN slug SET NOT NULL,
+            ADD CONSTRAINT projects_slug_regexp_check CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$');"#
+                .to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        manager
+            .create_index(
+                IndexCreateStatement::new()
+                    .name("projects_organization_id_slug_idx")
+                    .table(Projects::Table)
+                    .col(Projects::OrganizationId)
+                    .col(Projects::Slug)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("projects_organization_id_slug_idx")
+                    .table(Projects::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::Slug)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ColumnType {
+    #[iden = "character varying(63)"]
+    CharVarying,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Projects {
+    Table,
+    OrganizationId,
+    Slug,
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organizations::Table)
+                    .add_column(ColumnDef::new(Organizations::Description).text())
+                    .add_column(ColumnDef::new(Organizations::WebsiteUrl).text())
+                    .add_column(ColumnDef::new(Organizations::TwitterHandle).text())
+                    .add_column(ColumnDef::new(Organizations::InstagramHandle).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organizations::Table)
+                    .drop_column(Organizations::Description)
+                    .drop_column(Organizations::WebsiteUrl)
+                    .drop_column(Organizations::TwitterHandle)
+                    .drop_column(Organizations::InstagramHandle)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Organizations {
+    Table,
+    Description,
+    WebsiteUrl,
+    TwitterHandle,
+    InstagramHandle,
+}
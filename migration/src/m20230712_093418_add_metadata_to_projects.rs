@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(ColumnDef::new(Projects::Description).text())
+                    .add_column(ColumnDef::new(Projects::WebsiteUrl).text())
+                    .add_column(ColumnDef::new(Projects::TwitterHandle).text())
+                    .add_column(ColumnDef::new(Projects::InstagramHandle).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::Description)
+                    .drop_column(Projects::WebsiteUrl)
+                    .drop_column(Projects::TwitterHandle)
+                    .drop_column(Projects::InstagramHandle)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Projects {
+    Table,
+    Description,
+    WebsiteUrl,
+    TwitterHandle,
+    InstagramHandle,
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20221219_134917_create_projects_table::Projects;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiCredentials::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ApiCredentials::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra(" default gen_random_uuid()".to_string()),
+                    )
+                    .col(ColumnDef::new(ApiCredentials::ProjectId).uuid().not_null())
+                    .col(ColumnDef::new(ApiCredentials::Name).text().not_null())
+                    .col(
+                        ColumnDef::new(ApiCredentials::TokenPrefix)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ApiCredentials::SecretHash)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ApiCredentials::CreatedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(ApiCredentials::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("default now()".to_string()),
+                    )
+                    .col(ColumnDef::new(ApiCredentials::LastUsedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(ApiCredentials::RevokedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_credentials_project_id-projects")
+                            .from(ApiCredentials::Table, ApiCredentials::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                IndexCreateStatement::new()
+                    .name("api_credentials_project_id_idx")
+                    .table(ApiCredentials::Table)
+                    .col(ApiCredentials::ProjectId)
+                    .index_type(IndexType::Hash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiCredentials::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ApiCredentials {
+    Table,
+    Id,
+    ProjectId,
+    Name,
+    TokenPrefix,
+    SecretHash,
+    CreatedBy,
+    CreatedAt,
+    LastUsedAt,
+    RevokedAt,
+}
@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20221219_134917_create_projects_table::Projects;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProjectStats::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProjectStats::ProjectId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProjectStats::Drops)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProjectStats::Mints)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProjectStats::Customers)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProjectStats::Wallets)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProjectStats::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("default now()".to_string()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-project_stats_project_id-projects")
+                            .from(ProjectStats::Table, ProjectStats::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProjectStats::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ProjectStats {
+    Table,
+    ProjectId,
+    Drops,
+    Mints,
+    Customers,
+    Wallets,
+    UpdatedAt,
+}
@@ -0,0 +1,120 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Statement},
+};
+
+use crate::m20221215_150612_create_organizations_table::Organizations;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Plans::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Plans::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra(" default gen_random_uuid()".to_string()),
+                    )
+                    .col(ColumnDef::new(Plans::Name).text().not_null().unique_key())
+                    .col(ColumnDef::new(Plans::MaxProjects).integer())
+                    .col(ColumnDef::new(Plans::MaxMembers).integer())
+                    .col(ColumnDef::new(Plans::MaxPendingInvites).integer())
+                    .col(
+                        ColumnDef::new(Plans::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("default now()".to_string()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"INSERT INTO plans (name, max_projects, max_members, max_pending_invites) VALUES
+            ('free', 3, 5, 10),
+            ('pro', 25, 50, 100),
+            ('enterprise', NULL, NULL, NULL);"#
+                .to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organizations::Table)
+                    .add_column(ColumnDef::new(OrganizationPlans::PlanId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-organizations_plan_id-plans")
+                            .from_tbl(Organizations::Table)
+                            .from_col(OrganizationPlans::PlanId)
+                            .to_tbl(Plans::Table)
+                            .to_col(Plans::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"UPDATE organizations SET plan_id = (SELECT id FROM plans WHERE name = 'free');"#
+                .to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"ALTER TABLE organizations ALTER COLUMN plan_id SET NOT NULL;"#.to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organizations::Table)
+                    .drop_column(OrganizationPlans::PlanId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Plans::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Plans {
+    Table,
+    Id,
+    Name,
+    MaxProjects,
+    MaxMembers,
+    MaxPendingInvites,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum OrganizationPlans {
+    PlanId,
+}
@@ -0,0 +1,151 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Statement},
+};
+use sea_query::extension::postgres::Type;
+
+use crate::m20221215_150612_create_organizations_table::Organizations;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(CreditEntryKind::Type)
+                    .values([
+                        CreditEntryKind::Deposit,
+                        CreditEntryKind::Deduction,
+                        CreditEntryKind::Adjustment,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CreditLedger::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CreditLedger::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra(" default gen_random_uuid()".to_string()),
+                    )
+                    .col(
+                        ColumnDef::new(CreditLedger::OrganizationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CreditLedger::Kind)
+                            .custom(CreditEntryKind::Type)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CreditLedger::Amount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CreditLedger::Reference).text().not_null())
+                    .col(ColumnDef::new(CreditLedger::CreatedBy).uuid())
+                    .col(
+                        ColumnDef::new(CreditLedger::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("default now()".to_string()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-credit_ledger_organization_id-organizations")
+                            .from(CreditLedger::Table, CreditLedger::OrganizationId)
+                            .to(Organizations::Table, Organizations::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                IndexCreateStatement::new()
+                    .name("credit_ledger_organization_id_reference_idx")
+                    .table(CreditLedger::Table)
+                    .col(CreditLedger::OrganizationId)
+                    .col(CreditLedger::Reference)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"CREATE FUNCTION credit_ledger_append_only() RETURNS trigger AS $$
+            BEGIN
+                RAISE EXCEPTION 'credit_ledger is append-only';
+            END;
+            $$ LANGUAGE plpgsql;"#
+                .to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"CREATE TRIGGER credit_ledger_append_only
+            BEFORE UPDATE OR DELETE ON credit_ledger
+            FOR EACH ROW EXECUTE FUNCTION credit_ledger_append_only();"#
+                .to_string(),
+        );
+
+        db.execute(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CreditLedger::Table).to_owned())
+            .await?;
+
+        let stmt = Statement::from_string(
+            manager.get_database_backend(),
+            r#"DROP FUNCTION IF EXISTS credit_ledger_append_only();"#.to_string(),
+        );
+
+        manager.get_connection().execute(stmt).await?;
+
+        manager
+            .drop_type(Type::drop().name(CreditEntryKind::Type).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum CreditLedger {
+    Table,
+    Id,
+    OrganizationId,
+    Kind,
+    Amount,
+    Reference,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum CreditEntryKind {
+    #[iden = "credit_entry_kind"]
+    Type,
+    Deposit,
+    Deduction,
+    Adjustment,
+}
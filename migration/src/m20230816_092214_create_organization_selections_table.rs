@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20221215_150612_create_organizations_table::Organizations;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrganizationSelections::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OrganizationSelections::UserId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OrganizationSelections::OrganizationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrganizationSelections::SelectedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("default now()".to_string()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-organization_selections_organization_id-organizations")
+                            .from(
+                                OrganizationSelections::Table,
+                                OrganizationSelections::OrganizationId,
+                            )
+                            .to(Organizations::Table, Organizations::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(OrganizationSelections::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum OrganizationSelections {
+    Table,
+    UserId,
+    OrganizationId,
+    SelectedAt,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20221219_134917_create_projects_table::Projects;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProcessedEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProcessedEvents::Reference)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ProcessedEvents::ProjectId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(ProcessedEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("default now()".to_string()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-processed_events_project_id-projects")
+                            .from(ProcessedEvents::Table, ProcessedEvents::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProcessedEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ProcessedEvents {
+    Table,
+    Reference,
+    ProjectId,
+    CreatedAt,
+}